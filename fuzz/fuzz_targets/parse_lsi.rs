@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use preset_manager::presets::lsi_parser::parse_lsi_image;
+use preset_manager::presets::lsi_writer::write_lsi_image;
+
+fuzz_target!(|data: &[u8]| {
+    // Anything the parser accepts is written back to the same bytes
+    if let Ok(image) = parse_lsi_image(data) {
+        assert_eq!(write_lsi_image(&image), data);
+    }
+});
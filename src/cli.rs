@@ -0,0 +1,717 @@
+use anyhow::{anyhow, bail, Context};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use preset_manager::presets::backup::backup_bank;
+use preset_manager::presets::bank_diff::{diff_banks, BankDifference};
+use preset_manager::presets::compare::{compare_presets, ParameterChange};
+use preset_manager::presets::duplicates::find_duplicates;
+use preset_manager::presets::parameters::display_value;
+use preset_manager::presets::sort::{preset_name, SortKey};
+use preset_manager::presets::validate::{check_bank, repair_bank, Repair};
+use preset_manager::presets::{
+    load_presets, open_presets, operations, preset_filename_location, preset_index_from_location,
+    save_presets,
+};
+use preset_manager::types::{LoadedPresetEntry, PresetData, PresetType, Side};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::process::ExitCode;
+
+/// Manage Lovesynths preset banks. Run without arguments to open the editor window.
+#[derive(Parser, Debug)]
+#[command(name = "preset-manager", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: CliCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum CliCommand {
+    /// List the presets in a bank
+    List {
+        folder: String,
+        #[command(flatten)]
+        bank: BankArgs,
+    },
+    /// Move a preset to another slot, swapping it with the preset already there
+    Move {
+        folder: String,
+        /// Slot as an index or as row_column, e.g. 7 or 1_1
+        #[arg(value_parser = parse_slot)]
+        from: i32,
+        #[arg(value_parser = parse_slot)]
+        to: i32,
+        #[command(flatten)]
+        bank: BankArgs,
+        #[command(flatten)]
+        save: SaveArgs,
+    },
+    /// Swap two presets, both slots must hold a preset
+    Swap {
+        folder: String,
+        #[arg(value_parser = parse_slot)]
+        first: i32,
+        #[arg(value_parser = parse_slot)]
+        second: i32,
+        #[command(flatten)]
+        bank: BankArgs,
+        #[command(flatten)]
+        save: SaveArgs,
+    },
+    /// Copy a preset from one bank into a slot of another bank
+    CopyBetween {
+        from_folder: String,
+        #[arg(value_parser = parse_slot)]
+        from: i32,
+        to_folder: String,
+        #[arg(value_parser = parse_slot)]
+        to: i32,
+        /// Replace the preset in the target slot if there is one
+        #[arg(long)]
+        overwrite: bool,
+        #[command(flatten)]
+        bank: BankArgs,
+        #[command(flatten)]
+        save: SaveArgs,
+    },
+    /// Open an empty slot by moving the preset there and every preset after it one slot down
+    InsertSlot {
+        folder: String,
+        #[arg(value_parser = parse_slot)]
+        slot: i32,
+        #[command(flatten)]
+        bank: BankArgs,
+        #[command(flatten)]
+        save: SaveArgs,
+    },
+    /// Remove an empty slot, moving every preset after it one slot up
+    RemoveSlot {
+        folder: String,
+        #[arg(value_parser = parse_slot)]
+        slot: i32,
+        #[command(flatten)]
+        bank: BankArgs,
+        #[command(flatten)]
+        save: SaveArgs,
+    },
+    /// Close all gaps in a bank, keeping the order of its presets
+    Compact {
+        folder: String,
+        #[command(flatten)]
+        bank: BankArgs,
+        #[command(flatten)]
+        save: SaveArgs,
+    },
+    /// Sort the presets of a bank among the slots they occupy
+    Sort {
+        folder: String,
+        /// What to sort by
+        #[arg(long, value_enum, default_value_t = SortBy::Name)]
+        by: SortBy,
+        /// Path of the setting to sort by when sorting by parameter, e.g. filter.cutoff
+        #[arg(long, required_if_eq("by", "parameter"))]
+        parameter: Option<String>,
+        #[arg(long)]
+        descending: bool,
+        /// Print the new order without changing the bank
+        #[arg(long)]
+        dry_run: bool,
+        #[command(flatten)]
+        bank: BankArgs,
+        #[command(flatten)]
+        save: SaveArgs,
+    },
+    /// Compare two banks of the same type slot by slot
+    Diff {
+        left: String,
+        right: String,
+        /// Also list the settings that differ between presets in the same slot
+        #[arg(long)]
+        parameters: bool,
+        /// Exit with an error when the banks differ
+        #[arg(long)]
+        exit_code: bool,
+        #[command(flatten)]
+        bank: BankArgs,
+    },
+    /// List presets that occur more than once within or across banks
+    Duplicates {
+        #[arg(required = true)]
+        folders: Vec<String>,
+        /// Keep the first copy of every duplicate and delete the others, banks are searched in
+        /// the order given and each one by slot
+        #[arg(long)]
+        clear: bool,
+        #[command(flatten)]
+        bank: BankArgs,
+        #[command(flatten)]
+        save: SaveArgs,
+    },
+    /// Check banks for missing, broken and stray files, exits with an error if any has problems
+    Validate {
+        #[arg(required = true)]
+        folders: Vec<String>,
+        #[command(flatten)]
+        bank: BankArgs,
+    },
+    /// Fix the problems found by validate, backing the bank up first
+    Repair {
+        folder: String,
+        /// Leave out entries whose files are missing or broken
+        #[arg(long)]
+        drop_broken: bool,
+        /// Add the presets of settings files that are not in the preset list
+        #[arg(long)]
+        adopt_orphans: bool,
+        /// Move presets that share a slot with another one to free slots
+        #[arg(long)]
+        renumber: bool,
+        /// Name the files of every preset after its slot
+        #[arg(long)]
+        rename: bool,
+        /// Apply every repair
+        #[arg(long, conflicts_with_all = ["drop_broken", "adopt_orphans", "renumber", "rename"])]
+        all: bool,
+        #[command(flatten)]
+        bank: BankArgs,
+    },
+    /// Write a bank into another folder with freshly numbered files
+    Export {
+        folder: String,
+        output: String,
+        #[command(flatten)]
+        bank: BankArgs,
+    },
+}
+
+#[derive(Args, Debug)]
+struct BankArgs {
+    /// Type of the bank
+    #[arg(long = "type", value_enum, default_value_t = BankType::Timbre)]
+    bank_type: BankType,
+}
+
+#[derive(Args, Debug)]
+struct SaveArgs {
+    /// Do not back up the bank before overwriting it
+    #[arg(long)]
+    no_backup: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum BankType {
+    Timbre,
+    System,
+}
+
+impl From<BankType> for PresetType {
+    fn from(bank_type: BankType) -> Self {
+        match bank_type {
+            BankType::Timbre => PresetType::Timbre,
+            BankType::System => PresetType::System,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum SortBy {
+    Name,
+    OriginalIndex,
+    Modified,
+    Parameter,
+}
+
+fn parse_slot(slot: &str) -> Result<i32, String> {
+    slot.parse::<i32>()
+        .ok()
+        .or_else(|| preset_index_from_location(slot))
+        .filter(|i| *i >= 0)
+        .ok_or_else(|| format!("'{slot}' is not a slot index or row_column location"))
+}
+
+/// Whether the program was started with a subcommand or a flag, rather than e.g. with the path of
+/// a folder dropped onto it, which opens the editor window
+pub fn is_command_line() -> bool {
+    let Some(first) = std::env::args_os().nth(1) else {
+        return false;
+    };
+    let Some(first) = first.to_str() else {
+        return false;
+    };
+
+    first == "help"
+        || first.starts_with('-')
+        || Cli::command().get_subcommands().any(|command| {
+            command.get_name() == first || command.get_all_aliases().any(|alias| alias == first)
+        })
+}
+
+pub fn run() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run_command(cli.command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_command(command: CliCommand) -> anyhow::Result<()> {
+    match command {
+        CliCommand::List { folder, bank } => {
+            let preset_data = load(&folder, bank.bank_type.into(), Side::Left)?;
+            let mut indices = preset_data.presets.keys().copied().collect::<Vec<_>>();
+            indices.sort();
+            for i in indices {
+                let p = &preset_data.presets[&i];
+                let name = match &p.broken {
+                    Some(broken) => format!("broken: {}", broken.reason),
+                    None => preset_name(p).unwrap_or_default(),
+                };
+                println!(
+                    "{i:>4}  {:<6} {}  {}  {name}",
+                    preset_filename_location(i),
+                    p.preset_filename,
+                    p.image_filename,
+                );
+            }
+            Ok(())
+        }
+        CliCommand::Move {
+            folder,
+            from,
+            to,
+            bank,
+            save,
+        } => {
+            let mut preset_lists = load_sides(&folder, None, bank.bank_type.into())?;
+            let before = preset_lists[&Side::Left].presets.clone();
+            operations::move_preset(&mut preset_lists, from, Side::Left, to, Side::Left)
+                .map_err(|e| anyhow!(e))?;
+            store_changes(&before, &preset_lists[&Side::Left], &save)
+        }
+        CliCommand::Swap {
+            folder,
+            first,
+            second,
+            bank,
+            save,
+        } => {
+            let mut preset_lists = load_sides(&folder, None, bank.bank_type.into())?;
+            for slot in [first, second] {
+                if !preset_lists[&Side::Left].presets.contains_key(&slot) {
+                    bail!("Slot {} is empty, cannot swap", preset_filename_location(slot));
+                }
+            }
+            let before = preset_lists[&Side::Left].presets.clone();
+            operations::move_preset(&mut preset_lists, first, Side::Left, second, Side::Left)
+                .map_err(|e| anyhow!(e))?;
+            store_changes(&before, &preset_lists[&Side::Left], &save)
+        }
+        CliCommand::CopyBetween {
+            from_folder,
+            from,
+            to_folder,
+            to,
+            overwrite,
+            bank,
+            save,
+        } => {
+            let mut preset_lists =
+                load_sides(&from_folder, Some(&to_folder), bank.bank_type.into())?;
+            if !overwrite && preset_lists[&Side::Right].presets.contains_key(&to) {
+                bail!(
+                    "Slot {} of {to_folder} already holds a preset, use --overwrite to replace it",
+                    preset_filename_location(to)
+                );
+            }
+            let before = preset_lists[&Side::Right].presets.clone();
+            operations::copy_preset(&mut preset_lists, from, Side::Left, to, Side::Right)
+                .map_err(|e| anyhow!(e))?;
+            store_changes(&before, &preset_lists[&Side::Right], &save)
+        }
+        CliCommand::InsertSlot {
+            folder,
+            slot,
+            bank,
+            save,
+        } => {
+            let mut preset_lists = load_sides(&folder, None, bank.bank_type.into())?;
+            let before = preset_lists[&Side::Left].presets.clone();
+            operations::insert_slot(&mut preset_lists, Side::Left, slot).map_err(|e| anyhow!(e))?;
+            store_changes(&before, &preset_lists[&Side::Left], &save)
+        }
+        CliCommand::RemoveSlot {
+            folder,
+            slot,
+            bank,
+            save,
+        } => {
+            let mut preset_lists = load_sides(&folder, None, bank.bank_type.into())?;
+            let before = preset_lists[&Side::Left].presets.clone();
+            operations::remove_slot(&mut preset_lists, Side::Left, slot).map_err(|e| anyhow!(e))?;
+            store_changes(&before, &preset_lists[&Side::Left], &save)
+        }
+        CliCommand::Compact { folder, bank, save } => {
+            let mut preset_lists = load_sides(&folder, None, bank.bank_type.into())?;
+            let moved =
+                operations::compact_bank(&mut preset_lists, Side::Left).map_err(|e| anyhow!(e))?;
+            if moved == 0 {
+                println!("{folder} has no gaps");
+                return Ok(());
+            }
+            store(&preset_lists[&Side::Left], &save)?;
+            println!("Moved {moved} {}", if moved == 1 { "preset" } else { "presets" });
+            Ok(())
+        }
+        CliCommand::Sort {
+            folder,
+            by,
+            parameter,
+            descending,
+            dry_run,
+            bank,
+            save,
+        } => {
+            let key = match by {
+                SortBy::Name => SortKey::Name,
+                SortBy::OriginalIndex => SortKey::OriginalIndex,
+                SortBy::Modified => SortKey::Modified,
+                SortBy::Parameter => SortKey::Parameter(parameter.unwrap_or_default()),
+            };
+            // A dry run only reads the bank
+            let preset_data = if dry_run {
+                load(&folder, bank.bank_type.into(), Side::Left)?
+            } else {
+                open(&folder, bank.bank_type.into(), Side::Left)?
+            };
+            let before = preset_data.presets.clone();
+            let mut preset_lists = HashMap::from([(Side::Left, preset_data)]);
+            operations::sort_presets(&mut preset_lists, Side::Left, &[], &key, descending)
+                .map_err(|e| anyhow!(e))?;
+
+            let preset_data = &preset_lists[&Side::Left];
+            let mut indices = preset_data.presets.keys().copied().collect::<Vec<_>>();
+            indices.sort();
+            for i in indices {
+                let p = &preset_data.presets[&i];
+                println!(
+                    "{:<6} <- {:<6} {}",
+                    preset_filename_location(i),
+                    preset_filename_location(p.original_index),
+                    preset_name(p).unwrap_or_default()
+                );
+            }
+            if dry_run {
+                return Ok(());
+            }
+            store_changes(&before, preset_data, &save)
+        }
+        CliCommand::Diff {
+            left,
+            right,
+            parameters,
+            exit_code,
+            bank,
+        } => {
+            let left_data = load(&left, bank.bank_type.into(), Side::Left)?;
+            let right_data = load(&right, bank.bank_type.into(), Side::Right)?;
+            let (left_presets, right_presets) = (&left_data.presets, &right_data.presets);
+            let differences = diff_banks(left_presets, right_presets);
+            let name = |presets: &HashMap<i32, LoadedPresetEntry>, i: i32| {
+                preset_name(&presets[&i]).unwrap_or_default()
+            };
+
+            let mut changed = 0;
+            for difference in &differences {
+                match *difference {
+                    BankDifference::Identical(i) => {
+                        println!("= {:<14} {}", preset_filename_location(i), name(left_presets, i))
+                    }
+                    BankDifference::Moved { from, to } => {
+                        let slots = format!(
+                            "{} -> {}",
+                            preset_filename_location(from),
+                            preset_filename_location(to)
+                        );
+                        println!("> {slots:<14} {}", name(left_presets, from))
+                    }
+                    BankDifference::OnlyLeft(i) => println!(
+                        "- {:<14} {} (only in {left})",
+                        preset_filename_location(i),
+                        name(left_presets, i)
+                    ),
+                    BankDifference::OnlyRight(i) => println!(
+                        "+ {:<14} {} (only in {right})",
+                        preset_filename_location(i),
+                        name(right_presets, i)
+                    ),
+                    BankDifference::Differs { index, settings, image } => {
+                        let what = match (settings, image) {
+                            (true, true) => "settings and image differ",
+                            (true, false) => "settings differ",
+                            _ => "image differs",
+                        };
+                        println!(
+                            "~ {:<14} {} ({what})",
+                            preset_filename_location(index),
+                            name(left_presets, index)
+                        );
+                        if parameters && settings {
+                            print_parameter_diff(
+                                &left_presets[&index].preset_data,
+                                &right_presets[&index].preset_data,
+                            );
+                        }
+                    }
+                }
+                if !matches!(difference, BankDifference::Identical(_)) {
+                    changed += 1;
+                }
+            }
+            println!("{} identical, {changed} changed", differences.len() - changed);
+            if exit_code && changed > 0 {
+                bail!("The banks differ");
+            }
+            Ok(())
+        }
+        CliCommand::Duplicates {
+            folders,
+            clear,
+            bank,
+            save,
+        } => {
+            // A folder given twice would have every preset in it reported, and cleared, as a copy
+            let mut searched = HashSet::new();
+            let mut unique_folders = Vec::new();
+            for folder in folders {
+                let canonical =
+                    fs::canonicalize(&folder).with_context(|| format!("Cannot find {folder}"))?;
+                if searched.insert(canonical) {
+                    unique_folders.push(folder);
+                } else {
+                    eprintln!("warning: {folder} is given more than once, it is searched once");
+                }
+            }
+            let folders = unique_folders;
+            let mut banks = folders
+                .iter()
+                .map(|folder| {
+                    if clear {
+                        open(folder, bank.bank_type.into(), Side::Left)
+                    } else {
+                        load(folder, bank.bank_type.into(), Side::Left)
+                    }
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let groups = find_duplicates(&banks.iter().collect::<Vec<_>>());
+            if groups.is_empty() {
+                println!("No duplicates found");
+                return Ok(());
+            }
+
+            for group in &groups {
+                let first = group.presets[0];
+                println!(
+                    "{} copies of {}",
+                    group.presets.len(),
+                    preset_name(&banks[first.bank].presets[&first.index]).unwrap_or_default()
+                );
+                for location in &group.presets {
+                    println!(
+                        "  {:<6} {}",
+                        preset_filename_location(location.index),
+                        folders[location.bank]
+                    );
+                }
+            }
+            if !clear {
+                return Ok(());
+            }
+
+            let copies: Vec<_> = groups.iter().flat_map(|g| g.presets.iter().skip(1)).collect();
+            for location in &copies {
+                banks[location.bank].presets.remove(&location.index);
+            }
+            let changed: HashSet<usize> = copies.iter().map(|location| location.bank).collect();
+            for (i, preset_data) in banks.iter().enumerate() {
+                if changed.contains(&i) {
+                    store(preset_data, &save)?;
+                }
+            }
+            println!(
+                "Deleted {} {}",
+                copies.len(),
+                if copies.len() == 1 { "copy" } else { "copies" }
+            );
+            Ok(())
+        }
+        CliCommand::Validate { folders, bank } => {
+            let mut failed = 0;
+            for folder in &folders {
+                let check = check_bank(folder, bank.bank_type.into());
+                if check.is_ok() {
+                    println!("{folder}: ok, {} presets", check.presets.len());
+                    continue;
+                }
+                failed += 1;
+                let count = check.problems.len();
+                println!("{folder}: {count} {}", if count == 1 { "problem" } else { "problems" });
+                for problem in &check.problems {
+                    println!("  {problem}");
+                }
+                let repairs = check.applicable_repairs();
+                if !repairs.is_empty() {
+                    let repairs: Vec<String> = repairs.iter().map(repair_flag).collect();
+                    println!("  Run repair with {} to fix them", repairs.join(" "));
+                }
+            }
+            if failed > 0 {
+                bail!("{failed} of {} banks failed to validate", folders.len());
+            }
+            Ok(())
+        }
+        CliCommand::Repair {
+            folder,
+            drop_broken,
+            adopt_orphans,
+            renumber,
+            rename,
+            all,
+            bank,
+        } => {
+            let repairs: Vec<Repair> = [
+                (Repair::DropBroken, drop_broken),
+                (Repair::AdoptOrphans, adopt_orphans),
+                (Repair::Renumber, renumber),
+                (Repair::Rename, rename),
+            ]
+            .into_iter()
+            .filter(|(_, chosen)| *chosen || all)
+            .map(|(repair, _)| repair)
+            .collect();
+            if repairs.is_empty() {
+                bail!("Choose at least one repair, or --all");
+            }
+            let summary = repair_bank(&folder, bank.bank_type.into(), &repairs)
+                .with_context(|| format!("Cannot repair {folder}"))?;
+            println!("{folder}: {summary}");
+            Ok(())
+        }
+        CliCommand::Export {
+            folder,
+            output,
+            bank,
+        } => {
+            let preset_data = load(&folder, bank.bank_type.into(), Side::Left)?;
+            fs::create_dir_all(&output).with_context(|| format!("Cannot create {output}"))?;
+            save_presets(&output, &preset_data.presets, preset_data.preset_type)
+                .with_context(|| format!("Cannot export to {output}"))
+        }
+    }
+}
+
+fn repair_flag(repair: &Repair) -> String {
+    match repair {
+        Repair::DropBroken => "--drop-broken",
+        Repair::AdoptOrphans => "--adopt-orphans",
+        Repair::Renumber => "--renumber",
+        Repair::Rename => "--rename",
+    }
+    .to_string()
+}
+
+fn print_parameter_diff(left: &str, right: &str) {
+    let diffs = match compare_presets(left, right) {
+        Ok(diffs) => diffs,
+        Err(message) => {
+            println!("    {message}");
+            return;
+        }
+    };
+    for diff in diffs {
+        match diff.change {
+            ParameterChange::Changed { before, after } => println!(
+                "    {}: {} -> {}",
+                diff.path,
+                display_value(&before),
+                display_value(&after)
+            ),
+            ParameterChange::Added(value) => {
+                println!("    + {}: {}", diff.path, display_value(&value))
+            }
+            ParameterChange::Removed(value) => {
+                println!("    - {}: {}", diff.path, display_value(&value))
+            }
+        }
+    }
+}
+
+/// Loads a bank for reading it, leaving the folder as it is
+fn load(folder: &str, preset_type: PresetType, side: Side) -> anyhow::Result<PresetData> {
+    let preset_data =
+        load_presets(folder, preset_type, side).with_context(|| format!("Cannot load {folder}"))?;
+    warn_about_load(&preset_data);
+    Ok(preset_data)
+}
+
+/// Loads a bank for changing it, finishing an interrupted save first
+fn open(folder: &str, preset_type: PresetType, side: Side) -> anyhow::Result<PresetData> {
+    let preset_data =
+        open_presets(folder, preset_type, side).with_context(|| format!("Cannot open {folder}"))?;
+    warn_about_load(&preset_data);
+    Ok(preset_data)
+}
+
+fn warn_about_load(preset_data: &PresetData) {
+    if let Some(error) = &preset_data.metadata_error {
+        eprintln!("warning: {error}, the presets of {} have no metadata", preset_data.path);
+    }
+    if !preset_data.displaced.is_empty() {
+        eprintln!(
+            "warning: {} share a slot with an earlier preset, saving leaves them out. Run repair \
+             --renumber to keep them",
+            preset_data.displaced.join(", ")
+        );
+    }
+}
+
+/// Opens one or two banks for changing them the way the editor holds them, the first one on the
+/// left side
+fn load_sides(
+    left: &str,
+    right: Option<&str>,
+    preset_type: PresetType,
+) -> anyhow::Result<HashMap<Side, PresetData>> {
+    let mut preset_lists = HashMap::new();
+    preset_lists.insert(Side::Left, open(left, preset_type, Side::Left)?);
+    if let Some(right) = right {
+        preset_lists.insert(Side::Right, open(right, preset_type, Side::Right)?);
+    }
+
+    Ok(preset_lists)
+}
+
+/// Saves a bank a command changed. A command that left every slot as it was saves nothing, so no
+/// backup is made and no file is rewritten.
+fn store_changes(
+    before: &HashMap<i32, LoadedPresetEntry>,
+    preset_data: &PresetData,
+    save: &SaveArgs,
+) -> anyhow::Result<()> {
+    if preset_data.presets == *before {
+        println!("Nothing to do, {} is unchanged", preset_data.path);
+        return Ok(());
+    }
+    store(preset_data, save)
+}
+
+fn store(preset_data: &PresetData, save: &SaveArgs) -> anyhow::Result<()> {
+    if !save.no_backup {
+        backup_bank(&preset_data.path, preset_data.preset_type)
+            .with_context(|| format!("Cannot back up {}, nothing was saved", preset_data.path))?;
+    }
+    save_presets(&preset_data.path, &preset_data.presets, preset_data.preset_type)
+        .with_context(|| format!("Cannot save {}", preset_data.path))
+}
@@ -1,9 +1,14 @@
 use iced::Color;
 
 pub const PRESET_COLOR: Color = Color::from_rgb(0.65, 0.55, 0.98);
+pub const COMPARED_PRESET_COLOR: Color = Color::from_rgb(0.98, 0.62, 0.25);
 pub const PRESET_SELECTED_COLOR: Color = Color::from_rgb(0.58, 0.20, 0.92);
 pub const PRESET_HOVER_COLOR: Color = Color::from_rgb(0.44, 0.29, 0.68);
+pub const BROKEN_PRESET_COLOR: Color = Color::from_rgb(0.94, 0.27, 0.27);
+pub const BROKEN_PRESET_BACKGROUND_COLOR: Color = Color::from_rgb(0.22, 0.07, 0.09);
+pub const DROP_TARGET_COLOR: Color = Color::from_rgb(0.13, 0.77, 0.37);
 pub const BACKGROUND_COLOR: Color = Color::from_rgb(0.06, 0.09, 0.16);
 pub const HIGHLIGHT_BACKGROUND_COLOR: Color = Color::from_rgb(0.04, 0.05, 0.13);
 pub const PRIMARY_COLOR: Color = Color::from_rgb(0.36, 0.13, 0.71);
 pub const PRIMARY_HOVER_COLOR: Color = Color::from_rgb(0.30, 0.11, 0.58);
+pub const LABEL_COLOR: Color = Color::from_rgb(0.70, 0.70, 0.70);
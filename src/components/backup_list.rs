@@ -0,0 +1,80 @@
+use crate::styles::button_style::GeneralButtonStyle;
+use crate::AppMessage;
+use iced::theme::Button;
+use iced::widget::{button, column, container, row, scrollable, text};
+use iced::{Alignment, Element, Length};
+use preset_manager::presets::backup::BankBackup;
+use preset_manager::types::Side;
+
+pub fn backup_list(backups: &[BankBackup], side: Side) -> Element<'static, AppMessage> {
+    if backups.is_empty() {
+        return container(text("This bank has no backups yet"))
+            .padding([10, 16])
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into();
+    }
+
+    scrollable(
+        column(backups.iter().map(|b| {
+            let preset_count = match b.preset_count {
+                Some(1) => "1 preset".to_string(),
+                Some(n) => format!("{n} presets"),
+                None => "Cannot be loaded".to_string(),
+            };
+            let restore_button = button("Restore")
+                .style(Button::Custom(Box::new(GeneralButtonStyle::new())))
+                .padding([8, 12]);
+
+            row([
+                text(b.created.format("%Y-%m-%d %H:%M:%S").to_string())
+                    .width(Length::Fill)
+                    .into(),
+                text(preset_count).width(Length::Fixed(120.0)).into(),
+                if b.preset_count.is_some() {
+                    restore_button.on_press(AppMessage::RestoreBackup(side, b.path.clone()))
+                } else {
+                    restore_button
+                }
+                .into(),
+            ])
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .into()
+        }))
+        .spacing(10)
+        .padding([10, 20, 10, 16]),
+    )
+    .height(Length::Fill)
+    .width(Length::Fill)
+    .into()
+}
+
+pub fn backup_container(
+    name: &str,
+    backups: &[BankBackup],
+    side: Side,
+) -> Element<'static, AppMessage> {
+    column([
+        row([
+            text(format!("Backups of {name}"))
+                .size(22)
+                .width(Length::Fill)
+                .into(),
+            button("Back")
+                .style(Button::Custom(Box::new(GeneralButtonStyle::new())))
+                .padding([8, 12])
+                .on_press(AppMessage::HideBackups(side))
+                .into(),
+        ])
+        .width(Length::Fill)
+        .spacing(10)
+        .padding([12, 16])
+        .align_items(Alignment::Center)
+        .into(),
+        backup_list(backups, side),
+    ])
+    .height(Length::Fill)
+    .width(Length::Fill)
+    .into()
+}
@@ -0,0 +1,54 @@
+use crate::components::dialog::dialog_with_content;
+use crate::AppMessage;
+use iced::widget::{checkbox, column, container, scrollable, text};
+use iced::{Element, Length};
+use preset_manager::presets::validate::{BankCheck, Repair};
+use std::collections::HashSet;
+
+/// The problems found in a bank folder, with the repairs that apply to them
+pub fn bank_check_view(check: &BankCheck, chosen: &HashSet<Repair>) -> Element<'static, AppMessage> {
+    let summary = match check.problems.len() {
+        0 => format!("{}: no problems found", check.path),
+        1 => format!("{}: 1 problem", check.path),
+        count => format!("{}: {count} problems", check.path),
+    };
+    let problems = scrollable(
+        container(
+            column(
+                check
+                    .problems
+                    .iter()
+                    .map(|problem| text(problem.to_string()).size(13).into()),
+            )
+            .spacing(4),
+        )
+        .padding([0, 12, 0, 0]),
+    )
+    .height(Length::Fixed(240.0));
+
+    let repairs = check.applicable_repairs();
+    let mut content = column([text(summary).size(14).into()]).spacing(12);
+    if !check.is_ok() {
+        content = content.push(problems);
+    }
+    if !repairs.is_empty() {
+        content = content
+            .push(column(repairs.into_iter().map(|repair| {
+                checkbox(repair.to_string(), chosen.contains(&repair))
+                    .on_toggle(move |on| AppMessage::ToggleRepair(repair, on))
+                    .size(16)
+                    .text_size(14)
+                    .into()
+            }))
+            .spacing(6))
+            .push(text("The bank is backed up before it is repaired").size(12));
+    }
+
+    let mut buttons = Vec::new();
+    if check.is_repairable() && !check.is_ok() {
+        buttons.push(("Repair", AppMessage::RepairBank));
+    }
+    buttons.push(("Close", AppMessage::CloseBankCheck));
+
+    dialog_with_content("Check bank", content.into(), buttons)
+}
@@ -0,0 +1,118 @@
+use crate::components::dialog::dialog_with_content;
+use crate::AppMessage;
+use iced::theme::Text;
+use iced::widget::{column, container, row, scrollable, text};
+use iced::{Color, Element, Length};
+use preset_manager::presets::bank_diff::BankDifference;
+use preset_manager::presets::preset_filename_location;
+use preset_manager::presets::sort::preset_name;
+use preset_manager::types::PresetData;
+
+const MOVED_COLOR: Color = Color::from_rgb(0.36, 0.6, 0.98);
+const DIFFERS_COLOR: Color = Color::from_rgb(0.95, 0.75, 0.3);
+const ONLY_LEFT_COLOR: Color = Color::from_rgb(0.94, 0.27, 0.27);
+const ONLY_RIGHT_COLOR: Color = Color::from_rgb(0.13, 0.77, 0.37);
+
+fn name(preset_data: &PresetData, index: i32) -> String {
+    preset_data
+        .presets
+        .get(&index)
+        .and_then(preset_name)
+        .unwrap_or_default()
+}
+
+fn difference_row(
+    slots: String,
+    status: String,
+    color: Color,
+    name: String,
+) -> Element<'static, AppMessage> {
+    row([
+        text(slots).size(13).width(Length::FillPortion(2)).into(),
+        text(status)
+            .size(13)
+            .style(Text::Color(color))
+            .width(Length::FillPortion(3))
+            .into(),
+        text(name).size(13).width(Length::FillPortion(3)).into(),
+    ])
+    .spacing(8)
+    .into()
+}
+
+/// The slots in which two banks differ, identical presets are only counted
+pub fn bank_diff_view(
+    differences: &[BankDifference],
+    left: &PresetData,
+    right: &PresetData,
+) -> Element<'static, AppMessage> {
+    let identical = differences
+        .iter()
+        .filter(|d| matches!(d, BankDifference::Identical(_)))
+        .count();
+    let rows: Vec<Element<'static, AppMessage>> = differences
+        .iter()
+        .filter_map(|difference| {
+            let row = match *difference {
+                BankDifference::Identical(_) => return None,
+                BankDifference::Moved { from, to } => difference_row(
+                    format!(
+                        "{} -> {}",
+                        preset_filename_location(from),
+                        preset_filename_location(to)
+                    ),
+                    "Moved".to_string(),
+                    MOVED_COLOR,
+                    name(left, from),
+                ),
+                BankDifference::OnlyLeft(index) => difference_row(
+                    preset_filename_location(index),
+                    format!("Only in {}", left.name),
+                    ONLY_LEFT_COLOR,
+                    name(left, index),
+                ),
+                BankDifference::OnlyRight(index) => difference_row(
+                    preset_filename_location(index),
+                    format!("Only in {}", right.name),
+                    ONLY_RIGHT_COLOR,
+                    name(right, index),
+                ),
+                BankDifference::Differs { index, settings, image } => difference_row(
+                    preset_filename_location(index),
+                    match (settings, image) {
+                        (true, true) => "Settings and image differ",
+                        (true, false) => "Settings differ",
+                        _ => "Image differs",
+                    }
+                    .to_string(),
+                    DIFFERS_COLOR,
+                    name(left, index),
+                ),
+            };
+            Some(row)
+        })
+        .collect();
+
+    let summary = format!(
+        "{} and {}: {identical} identical, {} changed",
+        left.name,
+        right.name,
+        rows.len()
+    );
+    let content = if rows.is_empty() {
+        column([text(summary).into(), text("The banks hold the same presets").into()])
+    } else {
+        column([
+            text(summary).into(),
+            scrollable(container(column(rows).spacing(4)).padding([0, 12, 0, 0]))
+                .height(Length::Fixed(320.0))
+                .into(),
+        ])
+    };
+
+    dialog_with_content(
+        "Bank differences",
+        content.spacing(12).into(),
+        vec![("Close", AppMessage::CloseBankDiff)],
+    )
+}
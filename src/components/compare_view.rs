@@ -0,0 +1,143 @@
+use crate::colors::{COMPARED_PRESET_COLOR, PRESET_COLOR};
+use crate::components::dialog::dialog_with_content;
+use crate::components::preset_image::stroke_image;
+use crate::AppMessage;
+use iced::mouse::Cursor;
+use iced::theme::Text;
+use iced::widget::canvas::{Frame, Geometry, Program};
+use iced::widget::{column, container, row, scrollable, text, Canvas};
+use iced::{Alignment, Color, Element, Length, Rectangle, Renderer, Theme};
+use preset_manager::presets::compare::{compare_presets, ParameterChange};
+use preset_manager::presets::parameters::display_value;
+use preset_manager::presets::preset_filename_location;
+use preset_manager::types::{ImageData, LoadedPresetEntry, Side};
+
+const CHANGED_COLOR: Color = Color::from_rgb(0.95, 0.75, 0.3);
+const ADDED_COLOR: Color = Color::from_rgb(0.13, 0.77, 0.37);
+const REMOVED_COLOR: Color = Color::from_rgb(0.94, 0.27, 0.27);
+
+/// Both images drawn on top of each other, each in its own color
+struct OverlayCanvas {
+    images: [(ImageData, Color); 2],
+}
+
+impl<Message> Program<Message> for OverlayCanvas {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        for (image, color) in &self.images {
+            stroke_image(&mut frame, image, *color);
+        }
+        vec![frame.into_geometry()]
+    }
+}
+
+fn preset_label(side: Side, index: i32, color: Color) -> Element<'static, AppMessage> {
+    text(format!("{side:?} {}", preset_filename_location(index)))
+        .size(14)
+        .style(Text::Color(color))
+        .into()
+}
+
+fn diff_row(
+    path: String,
+    before: String,
+    after: String,
+    color: Color,
+) -> Element<'static, AppMessage> {
+    row([
+        text(path)
+            .size(13)
+            .style(Text::Color(color))
+            .width(Length::FillPortion(3))
+            .into(),
+        text(before).size(13).width(Length::FillPortion(2)).into(),
+        text(after).size(13).width(Length::FillPortion(2)).into(),
+    ])
+    .spacing(8)
+    .into()
+}
+
+/// The parameters that differ between two presets next to both images drawn over each other
+pub fn compare_view(
+    first: (Side, i32, &LoadedPresetEntry),
+    second: (Side, i32, &LoadedPresetEntry),
+) -> Element<'static, AppMessage> {
+    let (first_side, first_index, first_entry) = first;
+    let (second_side, second_index, second_entry) = second;
+
+    let diff: Element<'static, AppMessage> =
+        match compare_presets(&first_entry.preset_data, &second_entry.preset_data) {
+            Err(message) => text(message).size(14).into(),
+            Ok(diffs) if diffs.is_empty() => text("The settings are identical").size(14).into(),
+            Ok(diffs) => {
+                let rows = diffs.into_iter().map(|diff| match diff.change {
+                    ParameterChange::Changed { before, after } => diff_row(
+                        diff.path,
+                        display_value(&before),
+                        display_value(&after),
+                        CHANGED_COLOR,
+                    ),
+                    ParameterChange::Added(after) => {
+                        diff_row(diff.path, "-".to_string(), display_value(&after), ADDED_COLOR)
+                    }
+                    ParameterChange::Removed(before) => {
+                        diff_row(diff.path, display_value(&before), "-".to_string(), REMOVED_COLOR)
+                    }
+                });
+                scrollable(container(column(rows).spacing(4)).padding([0, 12, 0, 0]))
+                    .height(Length::Fixed(260.0))
+                    .into()
+            }
+        };
+
+    let overlay = Canvas::new(OverlayCanvas {
+        images: [
+            (first_entry.image.clone(), PRESET_COLOR),
+            (second_entry.image.clone(), COMPARED_PRESET_COLOR),
+        ],
+    })
+    .width(Length::Fixed(120.0))
+    .height(Length::Fixed(120.0));
+
+    let content = column([
+        row([
+            container(overlay).padding(10).into(),
+            column([
+                preset_label(first_side, first_index, PRESET_COLOR),
+                preset_label(second_side, second_index, COMPARED_PRESET_COLOR),
+                text("Changed parameters are yellow, added ones green and removed ones red")
+                    .size(12)
+                    .into(),
+            ])
+            .spacing(6)
+            .into(),
+        ])
+        .spacing(16)
+        .align_items(Alignment::Center)
+        .into(),
+        diff_row(
+            "Parameter".to_string(),
+            "First".to_string(),
+            "Second".to_string(),
+            Color::WHITE,
+        ),
+        diff,
+    ])
+    .spacing(10)
+    .into();
+
+    dialog_with_content(
+        "Compare presets",
+        content,
+        vec![("Close", AppMessage::CloseComparison)],
+    )
+}
@@ -0,0 +1,79 @@
+use crate::colors::BROKEN_PRESET_COLOR;
+use crate::components::metadata_fields::metadata_fields;
+use crate::components::parameter_inspector::{parameter_inspector, ParameterDraft};
+use crate::styles::button_style::GeneralButtonStyle;
+use crate::AppMessage;
+use iced::theme::{self, Button};
+use iced::widget::{button, column, container, row, scrollable, text};
+use iced::{Element, Length};
+use preset_manager::presets::preset_filename_location;
+use preset_manager::types::{LoadedPresetEntry, Side};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum DetailsTab {
+    #[default]
+    Metadata,
+    Parameters,
+}
+
+fn tab_button(label: &str, tab: DetailsTab, current: DetailsTab) -> Element<'static, AppMessage> {
+    let button = button(text(label).size(14))
+        .style(Button::Custom(Box::new(GeneralButtonStyle::new())))
+        .padding([4, 10]);
+    if tab == current {
+        button.into()
+    } else {
+        button.on_press(AppMessage::ShowDetailsTab(tab)).into()
+    }
+}
+
+/// Side panel with the metadata and settings of the selected preset
+pub fn details_panel(
+    tab: DetailsTab,
+    side: Side,
+    index: i32,
+    entry: &LoadedPresetEntry,
+    saved: Option<&LoadedPresetEntry>,
+    draft: Option<&ParameterDraft>,
+) -> Element<'static, AppMessage> {
+    let content = match tab {
+        DetailsTab::Metadata => metadata_fields(side, index, entry),
+        DetailsTab::Parameters => parameter_inspector(side, index, entry, saved, draft),
+    };
+
+    let mut header = column([
+        text(format!("Preset {}", preset_filename_location(index)))
+            .size(18)
+            .into(),
+        text(&entry.preset_filename).size(12).into(),
+    ])
+    .spacing(4);
+    if let Some(broken) = &entry.broken {
+        header = header.push(
+            text(format!("Could not be loaded: {}", broken.reason))
+                .size(12)
+                .style(theme::Text::Color(BROKEN_PRESET_COLOR)),
+        );
+    }
+
+    container(
+        column([
+            header.into(),
+            row([
+                tab_button("Metadata", DetailsTab::Metadata, tab),
+                tab_button("Parameters", DetailsTab::Parameters, tab),
+            ])
+            .spacing(8)
+            .into(),
+            scrollable(container(content).padding([0, 12, 0, 0]))
+                .height(Length::Fill)
+                .into(),
+        ])
+        .spacing(12)
+        .padding([12, 4, 12, 16]),
+    )
+    .style(theme::Container::Box)
+    .width(Length::Fixed(300.0))
+    .height(Length::Fill)
+    .into()
+}
@@ -0,0 +1,48 @@
+use crate::styles::button_style::GeneralButtonStyle;
+use crate::AppMessage;
+use iced::theme::{self, Button};
+use iced::widget::{button, column, container, row, text};
+use iced::{Alignment, Element, Length};
+
+/// A message with a row of buttons, shown in place of the editor until one of them is pressed
+pub fn dialog(
+    title: &str,
+    message: &str,
+    buttons: Vec<(&str, AppMessage)>,
+) -> Element<'static, AppMessage> {
+    dialog_with_content(title, text(message).into(), buttons)
+}
+
+pub fn dialog_with_content(
+    title: &str,
+    content: Element<'static, AppMessage>,
+    buttons: Vec<(&str, AppMessage)>,
+) -> Element<'static, AppMessage> {
+    container(
+        container(
+            column([
+                text(title).size(22).into(),
+                content,
+                row(buttons.into_iter().map(|(label, message)| {
+                    button(text(label))
+                        .style(Button::Custom(Box::new(GeneralButtonStyle::new())))
+                        .padding([8, 12])
+                        .on_press(message)
+                        .into()
+                }))
+                .spacing(10)
+                .into(),
+            ])
+            .spacing(16)
+            .align_items(Alignment::Start),
+        )
+        .style(theme::Container::Box)
+        .padding(24)
+        .max_width(560),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .center_x()
+    .center_y()
+    .into()
+}
@@ -0,0 +1,95 @@
+use crate::components::dialog::dialog_with_content;
+use crate::styles::button_style::GeneralButtonStyle;
+use crate::AppMessage;
+use iced::theme::Button;
+use iced::widget::{button, column, container, row, scrollable, text, Space};
+use iced::{Alignment, Element, Length};
+use preset_manager::presets::duplicates::DuplicateGroup;
+use preset_manager::presets::preset_filename_location;
+use preset_manager::presets::sort::preset_name;
+use preset_manager::types::{PresetData, Side};
+
+fn bank_label(side: Option<Side>, preset_data: &PresetData) -> String {
+    match side {
+        Some(side) => format!("{} ({side:?})", preset_data.name),
+        None => preset_data.path.clone(),
+    }
+}
+
+/// The presets found more than once in the searched banks, with a button on each to keep it and
+/// delete its copies from the open banks. `banks` are the searched banks with the side they are
+/// open on, in the order the groups refer to them.
+pub fn duplicates_view(
+    banks: &[(Option<Side>, &PresetData)],
+    groups: &[DuplicateGroup],
+) -> Element<'static, AppMessage> {
+    let searched = banks
+        .iter()
+        .map(|(side, preset_data)| bank_label(*side, preset_data))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let header = row([
+        text(format!("Searched {searched}")).size(14).width(Length::Fill).into(),
+        button(text("Add folder").size(14))
+            .style(Button::Custom(Box::new(GeneralButtonStyle::new())))
+            .padding([4, 10])
+            .on_press(AppMessage::AddDuplicatesFolder)
+            .into(),
+    ])
+    .spacing(10)
+    .align_items(Alignment::Center);
+
+    let list: Element<'static, AppMessage> = if groups.is_empty() {
+        text("No duplicates found").size(14).into()
+    } else {
+        let groups = groups.iter().map(|group| {
+            let first = group.presets[0];
+            let name = preset_name(&banks[first.bank].1.presets[&first.index]).unwrap_or_default();
+            let copies = group.presets.iter().map(|location| {
+                let (side, preset_data) = banks[location.bank];
+                row([
+                    text(preset_filename_location(location.index))
+                        .size(13)
+                        .width(Length::Fixed(50.0))
+                        .into(),
+                    text(bank_label(side, preset_data)).size(13).into(),
+                    Space::with_width(Length::Fill).into(),
+                    button(text("Keep").size(13))
+                        .style(Button::Custom(Box::new(GeneralButtonStyle::new())))
+                        .padding([2, 8])
+                        .on_press(AppMessage::KeepDuplicate(group.hash, *location))
+                        .into(),
+                ])
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .into()
+            });
+
+            column([
+                text(format!("{} copies of {name}", group.presets.len()))
+                    .size(15)
+                    .into(),
+                column(copies).spacing(4).into(),
+            ])
+            .spacing(6)
+            .into()
+        });
+        scrollable(container(column(groups).spacing(14)).padding([0, 12, 0, 0]))
+            .height(Length::Fixed(320.0))
+            .into()
+    };
+
+    dialog_with_content(
+        "Duplicates",
+        column([
+            header.into(),
+            list,
+            text("Keeping a preset deletes its copies from the open banks only")
+                .size(12)
+                .into(),
+        ])
+        .spacing(12)
+        .into(),
+        vec![("Close", AppMessage::CloseDuplicates)],
+    )
+}
@@ -0,0 +1,34 @@
+use crate::colors::LABEL_COLOR;
+use crate::components::dialog::dialog_with_content;
+use crate::AppMessage;
+use iced::theme::Text;
+use iced::widget::{column, row, text};
+use iced::{Element, Length};
+use preset_manager::presets::error::BankError;
+
+/// A failed bank operation with everything known about where it failed
+pub fn error_dialog(title: &str, error: &BankError) -> Element<'static, AppMessage> {
+    let details = error.details().into_iter().map(|(label, value)| {
+        row([
+            text(label)
+                .size(13)
+                .style(Text::Color(LABEL_COLOR))
+                .width(Length::Fixed(100.0))
+                .into(),
+            text(value).size(13).into(),
+        ])
+        .spacing(8)
+        .into()
+    });
+
+    dialog_with_content(
+        title,
+        column([
+            text(error.to_string()).size(14).into(),
+            column(details).spacing(4).into(),
+        ])
+        .spacing(12)
+        .into(),
+        vec![("Close", AppMessage::CloseError)],
+    )
+}
@@ -0,0 +1,80 @@
+use crate::AppMessage;
+use iced::widget::{column, text, text_input};
+use iced::Element;
+use preset_manager::types::{LoadedPresetEntry, PresetMetadata, Side};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MetadataField {
+    Name,
+    Author,
+    Category,
+    Tags,
+    Notes,
+}
+
+impl MetadataField {
+    fn value(self, metadata: &PresetMetadata) -> String {
+        match self {
+            MetadataField::Name => metadata.name.clone(),
+            MetadataField::Author => metadata.author.clone(),
+            MetadataField::Category => metadata.category.clone(),
+            MetadataField::Tags => metadata.tags.join(", "),
+            MetadataField::Notes => metadata.notes.clone(),
+        }
+    }
+
+    pub fn set(self, metadata: &mut PresetMetadata, value: String) {
+        match self {
+            MetadataField::Name => metadata.name = value,
+            MetadataField::Author => metadata.author = value,
+            MetadataField::Category => metadata.category = value,
+            MetadataField::Tags => {
+                // An empty last tag is kept so a comma can be typed before the next tag
+                let tags: Vec<&str> = value.split(',').map(str::trim).collect();
+                let last = tags.len() - 1;
+                metadata.tags = tags
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(i, t)| !t.is_empty() || (*i == last && *i > 0))
+                    .map(|(_, t)| t.to_string())
+                    .collect();
+            }
+            MetadataField::Notes => metadata.notes = value,
+        }
+    }
+}
+
+fn field(
+    label: &str,
+    placeholder: &str,
+    metadata_field: MetadataField,
+    metadata: &PresetMetadata,
+    side: Side,
+    index: i32,
+) -> Element<'static, AppMessage> {
+    column([
+        text(label).size(14).into(),
+        text_input(placeholder, &metadata_field.value(metadata))
+            .on_input(move |value| AppMessage::EditMetadata(side, index, metadata_field, value))
+            .size(14)
+            .padding([6, 8])
+            .into(),
+    ])
+    .spacing(4)
+    .into()
+}
+
+/// Fields to edit the metadata of a preset
+pub fn metadata_fields(side: Side, index: i32, entry: &LoadedPresetEntry) -> Element<'static, AppMessage> {
+    let metadata = &entry.metadata;
+
+    column([
+        field("Name", "Untitled", MetadataField::Name, metadata, side, index),
+        field("Author", "", MetadataField::Author, metadata, side, index),
+        field("Category", "e.g. Bass", MetadataField::Category, metadata, side, index),
+        field("Tags", "Separated by commas", MetadataField::Tags, metadata, side, index),
+        field("Notes", "", MetadataField::Notes, metadata, side, index),
+    ])
+    .spacing(12)
+    .into()
+}
@@ -1,2 +1,14 @@
+pub mod backup_list;
+pub mod bank_check_view;
+pub mod bank_diff_view;
+pub mod compare_view;
+pub mod details_panel;
+pub mod dialog;
+pub mod duplicates_view;
+pub mod error_dialog;
+pub mod metadata_fields;
+pub mod parameter_inspector;
 pub mod preset_list;
-pub mod preset_image;
\ No newline at end of file
+pub mod preset_image;
+pub mod sort_bar;
+pub mod toolbar;
\ No newline at end of file
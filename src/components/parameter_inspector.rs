@@ -0,0 +1,166 @@
+use crate::AppMessage;
+use iced::theme::Text;
+use iced::widget::{column, pick_list, row, slider, text, text_input, toggler};
+use iced::{Alignment, Color, Element, Length};
+use preset_manager::presets::parameters::{
+    display_value, number_value, parameter_control, parameter_sections, ParameterControl,
+};
+use preset_manager::types::{LoadedPresetEntry, Side};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A number being typed into a parameter field, kept until it is a valid value
+#[derive(Debug, Clone)]
+pub struct ParameterDraft {
+    pub side: Side,
+    pub index: i32,
+    pub path: String,
+    pub text: String,
+    pub error: Option<String>,
+}
+
+fn parameter_editor(
+    side: Side,
+    index: i32,
+    path: &str,
+    value: &Value,
+    draft: Option<&ParameterDraft>,
+) -> Element<'static, AppMessage> {
+    let set = {
+        let path = path.to_string();
+        move |value| AppMessage::SetParameter(side, index, path.clone(), value)
+    };
+
+    match parameter_control(path, value) {
+        ParameterControl::Toggle => toggler(None, value.as_bool().unwrap_or_default(), move |on| {
+            set(Value::Bool(on))
+        })
+        .width(Length::Shrink)
+        .into(),
+        ParameterControl::Slider { min, max, step } => {
+            let current = value.as_f64().unwrap_or(min);
+            // Whole numbers are kept only where the range takes nothing else
+            let integer = step >= 1.0;
+            let fallback = value.clone();
+            row([
+                slider(min..=max, current, move |v| {
+                    set(number_value(v, step, integer).unwrap_or_else(|| fallback.clone()))
+                })
+                .step(step)
+                .width(Length::Fill)
+                .into(),
+                text(display_value(value))
+                    .size(13)
+                    .width(Length::Fixed(44.0))
+                    .into(),
+            ])
+            .spacing(6)
+            .align_items(Alignment::Center)
+            .into()
+        }
+        ParameterControl::Choice(choices) => {
+            let current = value.as_str().map(str::to_string);
+            pick_list(choices, current, move |choice| set(Value::String(choice)))
+                .text_size(13)
+                .padding([2, 6])
+                .into()
+        }
+        ParameterControl::Number { .. } => {
+            let draft = draft.filter(|d| d.side == side && d.index == index && d.path == path);
+            let shown = draft.map_or_else(|| display_value(value), |d| d.text.clone());
+            let path = path.to_string();
+            let input = text_input("", &shown)
+                .on_input(move |text| {
+                    AppMessage::EditParameterText(side, index, path.clone(), text)
+                })
+                .size(13)
+                .padding([2, 6]);
+            match draft.and_then(|d| d.error.clone()) {
+                Some(error) => column([
+                    input.into(),
+                    text(error)
+                        .size(12)
+                        .style(Text::Color(Color::from_rgb(0.9, 0.4, 0.4)))
+                        .into(),
+                ])
+                .spacing(2)
+                .into(),
+                None => input.into(),
+            }
+        }
+        ParameterControl::Text => text_input("", value.as_str().unwrap_or_default())
+            .on_input(move |text| set(Value::String(text)))
+            .size(13)
+            .padding([2, 6])
+            .into(),
+        ParameterControl::ReadOnly => text(display_value(value)).size(13).into(),
+    }
+}
+
+/// The settings of a preset grouped into sections, with a control to edit each of them. Settings
+/// that differ from the saved bank are highlighted.
+pub fn parameter_inspector(
+    side: Side,
+    index: i32,
+    entry: &LoadedPresetEntry,
+    saved: Option<&LoadedPresetEntry>,
+    draft: Option<&ParameterDraft>,
+) -> Element<'static, AppMessage> {
+    if entry.broken.is_some() {
+        return text("The settings of a broken preset cannot be edited").size(14).into();
+    }
+    let sections = match parameter_sections(&entry.preset_data) {
+        Ok(sections) => sections,
+        Err(message) => return text(message).size(14).into(),
+    };
+    if sections.is_empty() {
+        return text("This preset has no settings").size(14).into();
+    }
+    let saved_values: HashMap<String, Value> = saved
+        .and_then(|saved| parameter_sections(&saved.preset_data).ok())
+        .into_iter()
+        .flatten()
+        .flat_map(|section| section.parameters)
+        .map(|parameter| (parameter.path, parameter.value))
+        .collect();
+
+    column(sections.into_iter().map(|section| {
+        column([
+            text(section.title).size(16).into(),
+            column(section.parameters.into_iter().map(|parameter| {
+                let changed = saved.is_some()
+                    && saved_values.get(&parameter.path) != Some(&parameter.value);
+                let label_color = if changed {
+                    Color::from_rgb(0.95, 0.75, 0.3)
+                } else {
+                    Color::from_rgb(0.7, 0.7, 0.7)
+                };
+                row([
+                    text(&parameter.path)
+                        .size(13)
+                        .style(Text::Color(label_color))
+                        .width(Length::FillPortion(2))
+                        .into(),
+                    column([parameter_editor(
+                        side,
+                        index,
+                        &parameter.path,
+                        &parameter.value,
+                        draft,
+                    )])
+                    .width(Length::FillPortion(3))
+                    .into(),
+                ])
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .into()
+            }))
+            .spacing(4)
+            .into(),
+        ])
+        .spacing(6)
+        .into()
+    }))
+    .spacing(16)
+    .into()
+}
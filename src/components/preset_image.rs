@@ -1,17 +1,23 @@
-use crate::types::{ImageData, LoadedPresetEntry, Side};
-use crate::styles::preset_button_style::PresetButtonStyle;
+use crate::styles::preset_slot_style::PresetSlotStyle;
 use crate::utils::make_display_point;
 use crate::AppMessage;
-use iced::mouse::Cursor;
-use iced::theme::Button;
+use iced::mouse::{Cursor, Interaction};
+use iced::theme;
 use iced::widget::canvas::{Frame, Geometry, Path, Program, Stroke};
-use iced::widget::{button, container, Canvas, Component};
-use iced::{Alignment, Color, Element, Length, Point, Rectangle, Renderer, Theme};
-use nom::Parser;
-use crate::colors::PRESET_COLOR;
+use iced::widget::tooltip::Position;
+use iced::widget::{container, mouse_area, text, tooltip, Canvas, MouseArea, Space};
+use iced::{Color, Element, Length, Rectangle, Renderer, Theme};
+use preset_manager::types::{ImageData, LoadedPresetEntry, Side};
+use std::collections::HashSet;
+use crate::colors::{BROKEN_PRESET_COLOR, PRESET_COLOR};
+use crate::fonts::{BROKEN_PRESET_ICON, FONT_AWESOME_SOLID};
+
+/// Width and height of a slot in the grid
+pub const SLOT_SIZE: f32 = 60.0;
 
 struct PresetImageCanvas {
     image: ImageData,
+    color: Color,
 }
 
 impl<Message> Program<Message> for PresetImageCanvas {
@@ -19,55 +25,155 @@ impl<Message> Program<Message> for PresetImageCanvas {
 
     fn draw(
         &self,
-        state: &Self::State,
+        _state: &Self::State,
         renderer: &Renderer,
-        theme: &Theme,
+        _theme: &Theme,
         bounds: Rectangle,
-        cursor: Cursor,
+        _cursor: Cursor,
     ) -> Vec<Geometry> {
         let mut frame = Frame::new(renderer, bounds.size());
+        stroke_image(&mut frame, &self.image, self.color);
+        vec![frame.into_geometry()]
+    }
+}
 
-        let path = Path::new(|builder| {
-            for line in &self.image {
-                let Some(first) = line.iter().next() else {
-                    continue;
-                };
+/// Draws the lines of a preset image scaled to the width of the frame
+pub fn stroke_image(frame: &mut Frame, image: &ImageData, color: Color) {
+    let path = Path::new(|builder| {
+        for line in image {
+            let Some(first) = line.iter().next() else {
+                continue;
+            };
 
-                builder.move_to(make_display_point(first, frame.width()));
-                for point in line.iter().skip(1) {
-                    builder.line_to(make_display_point(point, frame.width()));
-                }
+            builder.move_to(make_display_point(first, frame.width()));
+            for point in line.iter().skip(1) {
+                builder.line_to(make_display_point(point, frame.width()));
             }
-        });
+        }
+    });
 
-        frame.stroke(&path, Stroke::default().with_color(PRESET_COLOR));
-        vec![frame.into_geometry()]
-    }
+    frame.stroke(&path, Stroke::default().with_color(color));
+}
+
+/// Where the pointer and the selection are, for highlighting slots in the grid
+#[derive(Debug, Clone, Copy)]
+pub struct GridState<'a> {
+    pub selected: &'a HashSet<(i32, Side)>,
+    pub hovered: Option<(i32, Side)>,
+    // The slot a drag started on, set while the mouse button is held
+    pub dragged: Option<(i32, Side)>,
+    // The slots matching the search on the side being drawn, `None` when it has no search
+    pub matches: Option<&'a HashSet<i32>>,
+    // The slot the keyboard cursor is on, shown only on the active side
+    pub cursor: Option<(i32, Side)>,
+    pub active_side: Side,
 }
 
-pub fn preset_image(index: i32, entry: &LoadedPresetEntry, selected: Option<(i32, Side)>, side: Side) -> Element<'static, AppMessage> {
-    let style = if matches!(selected, Some((i, s)) if i == index && s == side) {
-        PresetButtonStyle::selected()
+impl GridState<'_> {
+    /// The selected slots on one side, in order
+    pub fn selected_on(&self, side: Side) -> Vec<i32> {
+        let mut indices: Vec<i32> = self
+            .selected
+            .iter()
+            .filter(|(_, s)| *s == side)
+            .map(|(i, _)| *i)
+            .collect();
+        indices.sort();
+        indices
+    }
+
+    /// Slots that do not match the search are drawn dimmed
+    fn dimmed(&self, index: i32) -> bool {
+        self.matches.is_some_and(|matches| !matches.contains(&index))
     }
-    else {
-        PresetButtonStyle::new()
+}
+
+fn preset_slot(
+    index: i32,
+    side: Side,
+    grid: GridState,
+    broken: bool,
+    content: Element<'static, AppMessage>,
+) -> MouseArea<'static, AppMessage, Theme, Renderer> {
+    let slot = Some((index, side));
+    let style = PresetSlotStyle {
+        selected: grid.selected.contains(&(index, side)),
+        hovered: grid.hovered == slot && grid.dragged.is_none(),
+        drop_target: grid.hovered == slot && grid.dragged.is_some_and(|d| Some(d) != slot),
+        dragged: grid.dragged == slot,
+        dimmed: grid.dimmed(index),
+        cursor: grid.cursor == slot && grid.active_side == side,
+        broken,
+    };
+
+    mouse_area(
+        container(content)
+            .width(SLOT_SIZE)
+            .height(SLOT_SIZE)
+            .padding([5, 10])
+            .style(theme::Container::Custom(Box::new(style))),
+    )
+    .on_press(AppMessage::PressPreset(index, side))
+    .on_release(AppMessage::ReleasePreset(index, side))
+    .on_enter(AppMessage::HoverPreset(index, side))
+    .on_exit(AppMessage::UnhoverPreset(index, side))
+    .interaction(if grid.dragged.is_some() {
+        Interaction::Grabbing
+    } else {
+        Interaction::Pointer
+    })
+}
+
+pub fn preset_image(index: i32, entry: &LoadedPresetEntry, grid: GridState, side: Side) -> Element<'static, AppMessage> {
+    let color = if grid.dimmed(index) {
+        Color {
+            a: 0.25,
+            ..PRESET_COLOR
+        }
+    } else {
+        PRESET_COLOR
+    };
+    let content: Element<'static, AppMessage> = match &entry.broken {
+        Some(_) => container(
+            text(BROKEN_PRESET_ICON)
+                .font(FONT_AWESOME_SOLID)
+                .size(20)
+                .style(theme::Text::Color(BROKEN_PRESET_COLOR)),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x()
+        .center_y()
+        .into(),
+        None => Canvas::new(PresetImageCanvas {
+            image: entry.image.clone(),
+            color,
+        })
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into(),
     };
 
-    button(Canvas::new(PresetImageCanvas {
-        image: entry.image.clone(),
-    }))
-    .on_press(AppMessage::ClickPreset(index, side))
-    .width(60)
-    .height(60)
-    .style(Button::Custom(Box::new(style)))
-    .into()
+    // Right clicking a selected preset deletes the whole selection on that side
+    let to_delete = if grid.selected.contains(&(index, side)) {
+        grid.selected_on(side)
+    } else {
+        vec![index]
+    };
+
+    let slot = preset_slot(index, side, grid, entry.broken.is_some(), content)
+        .on_right_press(AppMessage::DeletePresets(side, to_delete));
+    match &entry.broken {
+        Some(broken) => tooltip(slot, text(&broken.reason).size(13), Position::Bottom)
+            .style(theme::Container::Box)
+            .padding(6)
+            .into(),
+        None => slot.into(),
+    }
 }
 
-pub fn empty_preset_image(index: i32, side: Side) -> Element<'static, AppMessage> {
-    button("")
-        .on_press(AppMessage::ClickPreset(index, side))
-        .width(60)
-        .height(60)
-        .style(Button::Custom(Box::new(PresetButtonStyle::new())))
+pub fn empty_preset_image(index: i32, grid: GridState, side: Side) -> Element<'static, AppMessage> {
+    preset_slot(index, side, grid, false, Space::new(Length::Fill, Length::Fill).into())
+        .on_right_press(AppMessage::RemoveSlot(side, index))
         .into()
 }
@@ -1,26 +1,51 @@
-use crate::components::preset_image::{empty_preset_image, preset_image};
+use crate::colors::PRESET_SELECTED_COLOR;
+use crate::components::preset_image::{empty_preset_image, preset_image, GridState, SLOT_SIZE};
+use crate::fonts::{
+    CHECK_BANK_ICON, COMPACT_ICON, DELETE_ICON, FONT_AWESOME_SOLID, INSERT_SLOT_ICON, NEXT_MATCH_ICON,
+    PREVIOUS_MATCH_ICON, REDO_ICON, SORT_ICON, UNDO_ICON,
+};
 use crate::styles::button_style::GeneralButtonStyle;
-use crate::types::{LoadedPresetEntry, Side};
 use crate::AppMessage;
-use iced::theme::Button;
-use iced::widget::{button, column, container, row, scrollable, text};
-use iced::{Alignment, Application, Element, Font, Length};
-use itertools::Itertools;
-use nom::Parser;
+use iced::theme::{self, Button};
+use iced::widget::tooltip::Position;
+use iced::widget::{
+    button, column, container, row, scrollable, text, text_input, tooltip, Space,
+};
+use iced::{Alignment, Color, Element, Length};
+use preset_manager::presets::SYNTH_IMAGE_COLUMNS;
+use preset_manager::types::{LoadedPresetEntry, Side};
 use std::collections::HashMap;
-use std::hash::Hash;
+
+/// Space between two slots of the grid, and above its first row
+const GRID_SPACING: f32 = 10.0;
+
+/// The scrollable grid of a side, to keep the keyboard cursor in view
+pub fn grid_scroll_id(side: Side) -> scrollable::Id {
+    scrollable::Id::new(format!("grid-{side:?}"))
+}
+
+/// Number of rows shown for a bank, enough for the last preset plus one empty row
+pub fn grid_rows(presets: &HashMap<i32, LoadedPresetEntry>) -> i32 {
+    presets
+        .keys()
+        .max()
+        .map(|x| (x / SYNTH_IMAGE_COLUMNS) + 2)
+        .unwrap_or(1)
+}
+
+/// The top and bottom of a row of the grid, measured from the top of the scrolled content
+pub fn row_span(row: i32) -> (f32, f32) {
+    let top = GRID_SPACING + row as f32 * (SLOT_SIZE + GRID_SPACING);
+    (top, top + SLOT_SIZE)
+}
 
 pub fn preset_list(
     presets: &HashMap<i32, LoadedPresetEntry>,
-    selected: Option<(i32, Side)>,
+    grid: GridState,
     side: Side,
 ) -> Element<'static, AppMessage> {
-    let columns = 6;
-    // As many rows as required to show the lowest preset (plus at least 1)
-    let rows = presets.keys()
-        .max()
-        .map(|x| (x / columns) + 2)
-        .unwrap_or(1);
+    let columns = SYNTH_IMAGE_COLUMNS;
+    let rows = grid_rows(presets);
 
     scrollable(
         container(
@@ -28,33 +53,179 @@ pub fn preset_list(
                 row((0..columns).map(|c| {
                     let index = r * columns + c;
                     presets.get(&index).map_or_else(
-                        || empty_preset_image(index, side),
-                        |p| preset_image(index, p, selected, side),
+                        || empty_preset_image(index, grid, side),
+                        |p| preset_image(index, p, grid, side),
                     )
                 }))
-                .spacing(10)
+                .spacing(GRID_SPACING)
                 .into()
             }))
-            .spacing(10)
+            .spacing(GRID_SPACING)
             .align_items(Alignment::Start),
         )
         .center_x()
         .padding([10, 20, 10, 10]),
     )
+    .id(grid_scroll_id(side))
+    .on_scroll(move |viewport| AppMessage::GridScrolled(side, viewport))
     .height(Length::Fill)
     .width(Length::Fill)
     .into()
 }
 
+fn icon_button(icon: char, message: Option<AppMessage>, label: &str) -> Element<'static, AppMessage> {
+    let button = button(text(icon).font(FONT_AWESOME_SOLID))
+        .style(Button::Custom(Box::new(GeneralButtonStyle::new())))
+        .padding([8, 12]);
+    let button = match message {
+        Some(message) => button.on_press(message),
+        None => button,
+    };
+
+    tooltip(button, text(label).size(14), Position::Bottom)
+        .style(theme::Container::Box)
+        .padding(6)
+        .into()
+}
+
+/// The edit buttons shown between the bank header and the grid
+pub fn bank_tools(
+    grid: GridState,
+    side: Side,
+    can_undo: bool,
+    can_redo: bool,
+) -> Element<'static, AppMessage> {
+    let selected_here = grid.selected_on(side);
+    let insert_message = selected_here
+        .first()
+        .map(|i| AppMessage::InsertSlot(side, *i));
+    let delete_message =
+        (!selected_here.is_empty()).then_some(AppMessage::DeletePresets(side, selected_here));
+
+    row([
+        icon_button(UNDO_ICON, can_undo.then_some(AppMessage::Undo(side)), "Undo"),
+        icon_button(REDO_ICON, can_redo.then_some(AppMessage::Redo(side)), "Redo"),
+        icon_button(
+            DELETE_ICON,
+            delete_message,
+            "Delete selected presets (Del or right click)",
+        ),
+        icon_button(
+            INSERT_SLOT_ICON,
+            insert_message,
+            "Insert an empty slot before the selection (Insert over a slot)",
+        ),
+        icon_button(
+            COMPACT_ICON,
+            Some(AppMessage::CompactBank(side)),
+            "Close all gaps (right click an empty slot to close one)",
+        ),
+        icon_button(
+            SORT_ICON,
+            Some(AppMessage::StartSort(side)),
+            "Sort the bank, or the selected presets",
+        ),
+        icon_button(
+            CHECK_BANK_ICON,
+            Some(AppMessage::CheckBank(side)),
+            "Check the bank folder for problems and repair it",
+        ),
+    ])
+    .width(Length::Fill)
+    .spacing(10)
+    .padding([0, 16, 4, 16])
+    .align_items(Alignment::Center)
+    .into()
+}
+
+/// The search box of a side, for focusing it from the keyboard
+pub fn search_input_id(side: Side) -> text_input::Id {
+    text_input::Id::new(format!("search-{side:?}"))
+}
+
+/// A search box for the presets of a side, `match_count` is `None` while nothing is searched for
+pub fn search_bar(
+    side: Side,
+    query: &str,
+    match_count: Option<usize>,
+) -> Element<'static, AppMessage> {
+    let count: Element<'static, AppMessage> = match match_count {
+        Some(count) => {
+            let noun = if count == 1 { "match" } else { "matches" };
+            text(format!("{count} {noun}")).size(14).into()
+        }
+        None => Space::with_width(0).into(),
+    };
+    let has_matches = match_count.is_some_and(|count| count > 0);
+
+    row([
+        text_input("Search by name, tag, file or e.g. filter.cutoff > 0.5 (Ctrl+F)", query)
+            .id(search_input_id(side))
+            .on_input(move |query| AppMessage::Search(side, query))
+            .on_submit(AppMessage::NextMatch(side))
+            .size(14)
+            .padding([6, 8])
+            .into(),
+        count,
+        icon_button(
+            PREVIOUS_MATCH_ICON,
+            has_matches.then_some(AppMessage::PreviousMatch(side)),
+            "Previous match (Shift+F3)",
+        ),
+        icon_button(
+            NEXT_MATCH_ICON,
+            has_matches.then_some(AppMessage::NextMatch(side)),
+            "Next match (Enter or F3)",
+        ),
+    ])
+    .width(Length::Fill)
+    .spacing(10)
+    .padding([0, 16, 8, 16])
+    .align_items(Alignment::Center)
+    .into()
+}
+
 pub fn preset_container(
     name: &str,
     presets: &HashMap<i32, LoadedPresetEntry>,
-    selected: Option<(i32, Side)>,
+    grid: GridState,
     side: Side,
+    unsaved: bool,
+    search: Element<'static, AppMessage>,
+    tools: Element<'static, AppMessage>,
 ) -> Element<'static, AppMessage> {
+    let unsaved_marker: Element<'static, AppMessage> = if unsaved {
+        tooltip(
+            text("\u{25CF}").size(14).style(PRESET_SELECTED_COLOR),
+            text("Unsaved changes").size(14),
+            Position::Bottom,
+        )
+        .style(theme::Container::Box)
+        .padding(6)
+        .into()
+    } else {
+        Space::with_width(0).into()
+    };
+
+    // The side keyboard shortcuts apply to has a brighter name
+    let name_color = if grid.active_side == side {
+        Color::WHITE
+    } else {
+        Color::from_rgb(0.6, 0.6, 0.6)
+    };
+
     column([
         row([
-            text(name).size(22).width(Length::Fill).into(),
+            row([text(name).size(22).style(name_color).into(), unsaved_marker])
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .width(Length::Fill)
+                .into(),
+            button("Backups")
+                .style(Button::Custom(Box::new(GeneralButtonStyle::new())))
+                .padding([8, 12])
+                .on_press(AppMessage::ShowBackups(side))
+                .into(),
             button("Save")
                 .style(Button::Custom(Box::new(GeneralButtonStyle::new())))
                 .padding([8, 12])
@@ -71,7 +242,9 @@ pub fn preset_container(
         .padding([12, 16])
         .align_items(Alignment::Center)
         .into(),
-        preset_list(presets, selected, side),
+        search,
+        tools,
+        preset_list(presets, grid, side),
     ])
     .height(Length::Fill)
     .width(Length::Fill)
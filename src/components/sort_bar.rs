@@ -0,0 +1,113 @@
+use crate::styles::button_style::GeneralButtonStyle;
+use crate::AppMessage;
+use iced::theme::Button;
+use iced::widget::{button, checkbox, pick_list, row, text, text_input, Space};
+use iced::{Alignment, Element, Length};
+use preset_manager::presets::sort::SortKey;
+use preset_manager::types::Side;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum SortField {
+    #[default]
+    Name,
+    OriginalIndex,
+    Modified,
+    Parameter,
+}
+
+impl SortField {
+    const ALL: [SortField; 4] = [
+        SortField::Name,
+        SortField::OriginalIndex,
+        SortField::Modified,
+        SortField::Parameter,
+    ];
+}
+
+impl fmt::Display for SortField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SortField::Name => "Name",
+            SortField::OriginalIndex => "Original index",
+            SortField::Modified => "Modification date",
+            SortField::Parameter => "Parameter",
+        })
+    }
+}
+
+/// The sort being previewed on a side
+#[derive(Debug, Clone, Default)]
+pub struct SortOptions {
+    pub field: SortField,
+    // Path of the parameter to sort by, only used with `SortField::Parameter`
+    pub parameter: String,
+    pub descending: bool,
+}
+
+impl SortOptions {
+    pub fn key(&self) -> SortKey {
+        match self.field {
+            SortField::Name => SortKey::Name,
+            SortField::OriginalIndex => SortKey::OriginalIndex,
+            SortField::Modified => SortKey::Modified,
+            SortField::Parameter => SortKey::Parameter(self.parameter.trim().to_string()),
+        }
+    }
+}
+
+pub fn sort_bar(options: &SortOptions, side: Side, preset_count: usize) -> Element<'static, AppMessage> {
+    let update = move |options: SortOptions| AppMessage::UpdateSort(side, options);
+    let with_field = {
+        let options = options.clone();
+        move |field| update(SortOptions { field, ..options.clone() })
+    };
+    let with_parameter = {
+        let options = options.clone();
+        move |parameter| update(SortOptions { parameter, ..options.clone() })
+    };
+    let with_descending = {
+        let options = options.clone();
+        move |descending| update(SortOptions { descending, ..options.clone() })
+    };
+
+    let parameter_input: Element<'static, AppMessage> = if options.field == SortField::Parameter {
+        text_input("e.g. filter.cutoff", &options.parameter)
+            .on_input(with_parameter)
+            .size(14)
+            .padding([4, 8])
+            .width(Length::Fixed(140.0))
+            .into()
+    } else {
+        Space::with_width(0).into()
+    };
+
+    row([
+        text(format!("Sort {preset_count} presets by")).size(14).into(),
+        pick_list(&SortField::ALL[..], Some(options.field), with_field)
+            .text_size(14)
+            .padding([4, 8])
+            .into(),
+        parameter_input,
+        checkbox("Descending", options.descending)
+            .on_toggle(with_descending)
+            .text_size(14)
+            .into(),
+        Space::with_width(Length::Fill).into(),
+        button("Apply")
+            .style(Button::Custom(Box::new(GeneralButtonStyle::new())))
+            .padding([4, 10])
+            .on_press(AppMessage::ApplySort(side))
+            .into(),
+        button("Cancel")
+            .style(Button::Custom(Box::new(GeneralButtonStyle::new())))
+            .padding([4, 10])
+            .on_press(AppMessage::CancelSort(side))
+            .into(),
+    ])
+    .width(Length::Fill)
+    .spacing(10)
+    .padding([0, 16, 4, 16])
+    .align_items(Alignment::Center)
+    .into()
+}
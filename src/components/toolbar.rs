@@ -0,0 +1,58 @@
+use crate::styles::button_style::GeneralButtonStyle;
+use crate::AppMessage;
+use iced::theme::Button;
+use iced::widget::{button, pick_list, row, text, toggler, Space};
+use iced::{Alignment, Element, Length};
+use preset_manager::presets::operations::OccupiedSlots;
+
+/// `can_compare` enables comparing presets, which needs exactly two of them to be selected,
+/// `can_diff` comparing banks, which needs two banks of the same type to be open, and
+/// `can_find_duplicates` the duplicates search, which needs at least one open bank
+pub fn toolbar(
+    copy_mode: bool,
+    occupied_slots: OccupiedSlots,
+    can_compare: bool,
+    can_diff: bool,
+    can_find_duplicates: bool,
+) -> Element<'static, AppMessage> {
+    row([
+        toggler(
+            Some("Copy instead of move (or hold Alt)".to_string()),
+            copy_mode,
+            AppMessage::SetCopyMode,
+        )
+        .text_size(14)
+        .width(Length::Shrink)
+        .into(),
+        button(text("Compare").size(14))
+            .style(Button::Custom(Box::new(GeneralButtonStyle::new())))
+            .padding([4, 10])
+            .on_press_maybe(can_compare.then_some(AppMessage::ComparePresets))
+            .into(),
+        button(text("Compare banks").size(14))
+            .style(Button::Custom(Box::new(GeneralButtonStyle::new())))
+            .padding([4, 10])
+            .on_press_maybe(can_diff.then_some(AppMessage::ShowBankDiff))
+            .into(),
+        button(text("Find duplicates").size(14))
+            .style(Button::Custom(Box::new(GeneralButtonStyle::new())))
+            .padding([4, 10])
+            .on_press_maybe(can_find_duplicates.then_some(AppMessage::FindDuplicates))
+            .into(),
+        Space::with_width(Length::Fill).into(),
+        text("When target slots are taken").size(14).into(),
+        pick_list(
+            &OccupiedSlots::ALL[..],
+            Some(occupied_slots),
+            AppMessage::SetOccupiedSlots,
+        )
+        .text_size(14)
+        .padding([4, 8])
+        .into(),
+    ])
+    .width(Length::Fill)
+    .spacing(16)
+    .padding([8, 16, 0, 16])
+    .align_items(Alignment::Center)
+    .into()
+}
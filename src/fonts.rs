@@ -27,6 +27,17 @@ pub const FONT_AWESOME_SOLID: Font = Font {
     style: Style::Normal,
 };
 
+pub const UNDO_ICON: char = '\u{f0e2}';
+pub const REDO_ICON: char = '\u{f01e}';
+pub const DELETE_ICON: char = '\u{f1f8}';
+pub const INSERT_SLOT_ICON: char = '\u{f0fe}';
+pub const COMPACT_ICON: char = '\u{f066}';
+pub const SORT_ICON: char = '\u{f0dc}';
+pub const PREVIOUS_MATCH_ICON: char = '\u{f077}';
+pub const NEXT_MATCH_ICON: char = '\u{f078}';
+pub const BROKEN_PRESET_ICON: char = '\u{f071}';
+pub const CHECK_BANK_ICON: char = '\u{f0ad}';
+
 pub const FONT_AWESOME_SOLID_DATA: &[u8] = include_bytes!("../fonts/Font Awesome 6 Free-Solid-900.otf");
 
 pub fn load_fonts() -> Command<Result<(), iced::font::Error>> {
@@ -0,0 +1,526 @@
+use preset_manager::types::{LoadedPresetEntry, PresetData, Side};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum EditKind {
+    Move,
+    Swap,
+    Copy,
+    Delete,
+    RestoreBackup,
+    InsertSlot,
+    RemoveSlot,
+    Compact,
+    Sort,
+    Metadata,
+    Parameter,
+}
+
+impl EditKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            EditKind::Move => "move",
+            EditKind::Swap => "swap",
+            EditKind::Copy => "copy",
+            EditKind::Delete => "delete",
+            EditKind::RestoreBackup => "backup restore",
+            EditKind::InsertSlot => "slot insert",
+            EditKind::RemoveSlot => "slot removal",
+            EditKind::Compact => "compact",
+            EditKind::Sort => "sort",
+            EditKind::Metadata => "metadata edit",
+            EditKind::Parameter => "parameter edit",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SlotChange {
+    side: Side,
+    index: i32,
+    before: Option<LoadedPresetEntry>,
+    after: Option<LoadedPresetEntry>,
+}
+
+impl SlotChange {
+    /// Whether the slot ended up as it was, apart from the time its settings were changed
+    fn is_undone(&self) -> bool {
+        let contents = |entry: &Option<LoadedPresetEntry>| {
+            entry.clone().map(|entry| LoadedPresetEntry {
+                modified: None,
+                ..entry
+            })
+        };
+        contents(&self.before) == contents(&self.after)
+    }
+}
+
+/// A bank edit that can be undone, stored as the contents of every slot it touched before and
+/// after the edit
+#[derive(Debug)]
+pub struct Edit {
+    id: u64,
+    kind: EditKind,
+    changes: Vec<SlotChange>,
+    // Consecutive edits with the same key are merged into one, so typing a word is undone at once
+    merge_key: Option<String>,
+}
+
+impl Edit {
+    /// Runs `operation` and records how it changed the given slots. The slots must cover everything
+    /// the operation touches, otherwise undoing it leaves the other slots in their new state.
+    pub fn record<T>(
+        kind: EditKind,
+        preset_lists: &mut HashMap<Side, PresetData>,
+        slots: impl IntoIterator<Item = (Side, i32)>,
+        operation: impl FnOnce(&mut HashMap<Side, PresetData>) -> Result<T, String>,
+    ) -> Result<(T, Edit), String> {
+        let mut slots: Vec<(Side, i32)> = slots.into_iter().collect();
+        slots.sort_by_key(|(side, index)| (*side == Side::Right, *index));
+        slots.dedup();
+
+        let slot_contents = |lists: &HashMap<Side, PresetData>, side: Side, index: i32| {
+            lists.get(&side).and_then(|p| p.presets.get(&index)).cloned()
+        };
+        let before: Vec<Option<LoadedPresetEntry>> = slots
+            .iter()
+            .map(|(side, index)| slot_contents(preset_lists, *side, *index))
+            .collect();
+
+        let result = operation(preset_lists)?;
+
+        let changes = slots
+            .into_iter()
+            .zip(before)
+            .map(|((side, index), before)| SlotChange {
+                side,
+                index,
+                before,
+                after: slot_contents(preset_lists, side, index),
+            })
+            .filter(|change| change.before != change.after)
+            .collect();
+
+        Ok((
+            result,
+            Edit {
+                id: 0,
+                kind,
+                changes,
+                merge_key: None,
+            },
+        ))
+    }
+
+    /// Like `record`, for operations that can touch any slot of the given sides. The sides are
+    /// compared before and after the operation to find the slots it changed.
+    pub fn record_sides<T>(
+        kind: EditKind,
+        preset_lists: &mut HashMap<Side, PresetData>,
+        sides: &[Side],
+        operation: impl FnOnce(&mut HashMap<Side, PresetData>) -> Result<T, String>,
+    ) -> Result<(T, Edit), String> {
+        let before: Vec<(Side, HashMap<i32, LoadedPresetEntry>)> = sides
+            .iter()
+            .filter_map(|side| preset_lists.get(side).map(|p| (*side, p.presets.clone())))
+            .collect();
+
+        let result = operation(preset_lists)?;
+
+        let mut changes = Vec::new();
+        for (side, before) in before {
+            let after = &preset_lists[&side].presets;
+            let mut indices: Vec<i32> = before.keys().chain(after.keys()).copied().collect();
+            indices.sort();
+            indices.dedup();
+            for index in indices {
+                if before.get(&index) != after.get(&index) {
+                    changes.push(SlotChange {
+                        side,
+                        index,
+                        before: before.get(&index).cloned(),
+                        after: after.get(&index).cloned(),
+                    });
+                }
+            }
+        }
+
+        Ok((
+            result,
+            Edit {
+                id: 0,
+                kind,
+                changes,
+                merge_key: None,
+            },
+        ))
+    }
+
+    /// Makes the edit merge into the previous one if that has the same key
+    pub fn merging(mut self, key: String) -> Edit {
+        self.merge_key = Some(key);
+        self
+    }
+
+    pub fn kind(&self) -> EditKind {
+        self.kind
+    }
+
+    /// Whether the edit left every slot as it was
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    fn sides(&self) -> Vec<Side> {
+        let mut sides: Vec<Side> = self.changes.iter().map(|c| c.side).collect();
+        sides.dedup();
+        sides
+    }
+
+    fn apply(&self, preset_lists: &mut HashMap<Side, PresetData>) {
+        for change in &self.changes {
+            set_slot(preset_lists, change.side, change.index, change.after.clone());
+        }
+    }
+
+    fn revert(&self, preset_lists: &mut HashMap<Side, PresetData>) {
+        for change in &self.changes {
+            set_slot(preset_lists, change.side, change.index, change.before.clone());
+        }
+    }
+}
+
+fn set_slot(
+    preset_lists: &mut HashMap<Side, PresetData>,
+    side: Side,
+    index: i32,
+    entry: Option<LoadedPresetEntry>,
+) {
+    let Some(preset_data) = preset_lists.get_mut(&side) else {
+        return;
+    };
+    match entry {
+        Some(entry) => preset_data.presets.insert(index, entry),
+        None => preset_data.presets.remove(&index),
+    };
+}
+
+#[derive(Debug, Default)]
+struct SideHistory {
+    undo: Vec<Rc<Edit>>,
+    redo: Vec<Rc<Edit>>,
+}
+
+/// Undo and redo stacks for both sides. An edit that moves presets between the sides is on the
+/// stacks of both and can only be undone or redone while it is the newest edit on both of them.
+#[derive(Debug, Default)]
+pub struct EditHistory {
+    sides: HashMap<Side, SideHistory>,
+    next_id: u64,
+}
+
+impl EditHistory {
+    /// Adds an edit to the undo stacks of the sides it changed, edits that changed nothing are
+    /// dropped so undo never has to step over them. An edit merged into the previous one that
+    /// takes a slot back to how it was, like a slider dragged back to its start, puts the slot
+    /// back exactly in `preset_lists` and drops its change.
+    pub fn push(&mut self, mut edit: Edit, preset_lists: &mut HashMap<Side, PresetData>) {
+        if edit.is_empty() {
+            return;
+        }
+        if let Some(previous) = self.mergeable_edit(&edit) {
+            for side in previous.sides() {
+                self.sides.get_mut(&side).unwrap().undo.pop();
+            }
+            for change in &mut edit.changes {
+                let earlier = previous
+                    .changes
+                    .iter()
+                    .find(|c| c.side == change.side && c.index == change.index);
+                if let Some(earlier) = earlier {
+                    change.before = earlier.before.clone();
+                }
+            }
+            edit.changes.retain(|change| {
+                if change.is_undone() {
+                    set_slot(preset_lists, change.side, change.index, change.before.clone());
+                }
+                !change.is_undone()
+            });
+            if edit.is_empty() {
+                return;
+            }
+        }
+
+        self.next_id += 1;
+        edit.id = self.next_id;
+        let edit = Rc::new(edit);
+
+        for side in edit.sides() {
+            self.clear_redo(side);
+            self.sides.entry(side).or_default().undo.push(edit.clone());
+        }
+    }
+
+    pub fn undo(
+        &mut self,
+        side: Side,
+        preset_lists: &mut HashMap<Side, PresetData>,
+    ) -> Result<EditKind, String> {
+        let Some(edit) = self.sides.get(&side).and_then(|h| h.undo.last()).cloned() else {
+            return Err("Nothing to undo".to_string());
+        };
+        self.check_newest(&edit, |h| &h.undo, "undo")?;
+
+        edit.revert(preset_lists);
+        for side in edit.sides() {
+            let history = self.sides.entry(side).or_default();
+            history.undo.pop();
+            history.redo.push(edit.clone());
+        }
+
+        Ok(edit.kind())
+    }
+
+    pub fn redo(
+        &mut self,
+        side: Side,
+        preset_lists: &mut HashMap<Side, PresetData>,
+    ) -> Result<EditKind, String> {
+        let Some(edit) = self.sides.get(&side).and_then(|h| h.redo.last()).cloned() else {
+            return Err("Nothing to redo".to_string());
+        };
+        self.check_newest(&edit, |h| &h.redo, "redo")?;
+
+        edit.apply(preset_lists);
+        for side in edit.sides() {
+            let history = self.sides.entry(side).or_default();
+            history.redo.pop();
+            history.undo.push(edit.clone());
+        }
+
+        Ok(edit.kind())
+    }
+
+    pub fn can_undo(&self, side: Side) -> bool {
+        self.sides.get(&side).is_some_and(|h| !h.undo.is_empty())
+    }
+
+    pub fn can_redo(&self, side: Side) -> bool {
+        self.sides.get(&side).is_some_and(|h| !h.redo.is_empty())
+    }
+
+    /// Forgets the history of a side, e.g. when it is closed or another bank is loaded into it.
+    /// Edits on the other side that are older than its last edit involving this side can no longer
+    /// be undone and are dropped as well.
+    pub fn clear_side(&mut self, side: Side) {
+        self.sides.remove(&side);
+
+        let Some(other) = self.sides.get_mut(&side.other()) else {
+            return;
+        };
+        if let Some(newest_shared) = other.undo.iter().rposition(|e| e.sides().contains(&side)) {
+            other.undo.drain(..=newest_shared);
+        }
+        if other.redo.iter().any(|e| e.sides().contains(&side)) {
+            other.redo.clear();
+        }
+    }
+
+    /// The newest edit, if `edit` can be merged into it
+    fn mergeable_edit(&self, edit: &Edit) -> Option<Rc<Edit>> {
+        edit.merge_key.as_ref()?;
+        let sides = edit.sides();
+        let previous = self.sides.get(sides.first()?)?.undo.last()?.clone();
+        let newest_everywhere = sides.iter().all(|side| {
+            let newest = self.sides.get(side).and_then(|h| h.undo.last());
+            newest.is_some_and(|e| e.id == previous.id)
+        });
+
+        (newest_everywhere && previous.sides() == sides && previous.merge_key == edit.merge_key)
+            .then_some(previous)
+    }
+
+    fn check_newest(
+        &self,
+        edit: &Edit,
+        stack: impl Fn(&SideHistory) -> &Vec<Rc<Edit>>,
+        action: &str,
+    ) -> Result<(), String> {
+        for side in edit.sides() {
+            let newest = self.sides.get(&side).and_then(|h| stack(h).last());
+            if newest.map(|e| e.id) != Some(edit.id) {
+                let side_name = match side {
+                    Side::Left => "left",
+                    Side::Right => "right",
+                };
+                return Err(format!(
+                    "This {} involves the {side_name} side, {action} the newer changes there first",
+                    edit.kind().label()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn clear_redo(&mut self, side: Side) {
+        let Some(history) = self.sides.get_mut(&side) else {
+            return;
+        };
+        let shared_with_other = history
+            .redo
+            .iter()
+            .any(|e| e.sides().contains(&side.other()));
+        history.redo.clear();
+
+        // A redo entry shared with the other side can't be redone anymore, and neither can
+        // anything that was undone before it
+        if shared_with_other {
+            if let Some(other) = self.sides.get_mut(&side.other()) {
+                other.redo.clear();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use preset_manager::types::PresetType;
+    use std::time::SystemTime;
+
+    fn entry(index: i32, settings: &str) -> LoadedPresetEntry {
+        LoadedPresetEntry::new(
+            index,
+            Side::Left,
+            String::new(),
+            String::new(),
+            Vec::new(),
+            settings.to_string(),
+            None,
+        )
+    }
+
+    /// A left side holding one preset in slot 0 with the given settings
+    fn preset_lists(settings: &str) -> HashMap<Side, PresetData> {
+        let preset_data = PresetData {
+            path: String::new(),
+            preset_type: PresetType::Timbre,
+            presets: HashMap::from([(0, entry(0, settings))]),
+            name: String::new(),
+            metadata_error: None,
+            displaced: Vec::new(),
+        };
+        HashMap::from([(Side::Left, preset_data)])
+    }
+
+    fn settings(preset_lists: &HashMap<Side, PresetData>) -> &str {
+        &preset_lists[&Side::Left].presets[&0].preset_data
+    }
+
+    /// Records setting the settings in slot 0, which like editing a parameter marks them modified
+    fn set_settings(preset_lists: &mut HashMap<Side, PresetData>, settings: &str) -> Edit {
+        let slots = [(Side::Left, 0)];
+        let ((), edit) = Edit::record(EditKind::Parameter, preset_lists, slots, |lists| {
+            let entry = LoadedPresetEntry {
+                modified: Some(SystemTime::now()),
+                ..entry(0, settings)
+            };
+            lists.get_mut(&Side::Left).unwrap().presets.insert(0, entry);
+            Ok(())
+        })
+        .unwrap();
+        edit
+    }
+
+    /// Records setting the settings in slot 0 and pushes it, merging by `key` when there is one
+    fn push_settings(
+        history: &mut EditHistory,
+        preset_lists: &mut HashMap<Side, PresetData>,
+        settings: &str,
+        key: Option<&str>,
+    ) {
+        let edit = set_settings(preset_lists, settings);
+        match key {
+            Some(key) => history.push(edit.merging(key.to_string()), preset_lists),
+            None => history.push(edit, preset_lists),
+        }
+    }
+
+    #[test]
+    fn edits_that_change_nothing_are_not_kept() {
+        let mut lists = preset_lists("a");
+        let mut history = EditHistory::default();
+        let slots = [(Side::Left, 0), (Side::Left, 1)];
+        let ((), edit) =
+            Edit::record(EditKind::Parameter, &mut lists, slots, |_| Ok(())).unwrap();
+
+        assert!(edit.is_empty());
+        history.push(edit, &mut lists);
+        assert!(!history.can_undo(Side::Left));
+        assert_eq!(settings(&lists), "a");
+    }
+
+    #[test]
+    fn edits_with_the_same_key_are_undone_at_once() {
+        let mut lists = preset_lists("a");
+        let mut history = EditHistory::default();
+        for settings in ["ab", "abc"] {
+            push_settings(&mut history, &mut lists, settings, Some("name"));
+        }
+
+        history.undo(Side::Left, &mut lists).unwrap();
+        assert_eq!(settings(&lists), "a");
+        assert!(!history.can_undo(Side::Left));
+
+        history.redo(Side::Left, &mut lists).unwrap();
+        assert_eq!(settings(&lists), "abc");
+    }
+
+    #[test]
+    fn edits_with_other_keys_or_none_are_kept_apart() {
+        let mut lists = preset_lists("a");
+        let mut history = EditHistory::default();
+        push_settings(&mut history, &mut lists, "b", Some("name"));
+        push_settings(&mut history, &mut lists, "c", Some("notes"));
+        push_settings(&mut history, &mut lists, "d", None);
+        push_settings(&mut history, &mut lists, "e", Some("name"));
+
+        for expected in ["d", "c", "b", "a"] {
+            history.undo(Side::Left, &mut lists).unwrap();
+            assert_eq!(settings(&lists), expected);
+        }
+        assert!(!history.can_undo(Side::Left));
+    }
+
+    #[test]
+    fn an_edit_is_not_merged_into_one_that_was_undone() {
+        let mut lists = preset_lists("a");
+        let mut history = EditHistory::default();
+        push_settings(&mut history, &mut lists, "b", Some("name"));
+        history.undo(Side::Left, &mut lists).unwrap();
+        push_settings(&mut history, &mut lists, "c", Some("name"));
+
+        assert!(!history.can_redo(Side::Left));
+        history.undo(Side::Left, &mut lists).unwrap();
+        assert_eq!(settings(&lists), "a");
+    }
+
+    #[test]
+    fn merged_edits_that_end_where_they_started_are_dropped() {
+        let mut lists = preset_lists("a");
+        let mut history = EditHistory::default();
+        push_settings(&mut history, &mut lists, "b", None);
+        let started = lists[&Side::Left].presets[&0].clone();
+        push_settings(&mut history, &mut lists, "c", Some("name"));
+        push_settings(&mut history, &mut lists, "b", Some("name"));
+
+        // The slot is as it was, down to when its settings were changed
+        assert_eq!(lists[&Side::Left].presets[&0], started);
+        history.undo(Side::Left, &mut lists).unwrap();
+        assert_eq!(settings(&lists), "a");
+        assert!(!history.can_undo(Side::Left));
+    }
+}
@@ -0,0 +1,11 @@
+//! Reading and writing of Lovesynths preset banks.
+//!
+//! A bank is a folder holding a preset list (`preset_list.json` for timbres,
+//! `system_preset_list.json` for system presets) and, for every preset in it, a settings JSON file
+//! and a drawing in the LSI image format. [`presets::load_presets`] reads a whole bank into a
+//! [`types::PresetData`] and [`presets::save_presets`] writes one back, renaming the files after
+//! the slots the presets ended up in. The LSI format itself is handled by
+//! [`presets::lsi_parser::parse_lsi_image`] and [`presets::lsi_writer::write_lsi_image`].
+
+pub mod presets;
+pub mod types;
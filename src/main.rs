@@ -1,46 +1,98 @@
 #![windows_subsystem = "windows"]
 
+mod cli;
 pub mod colors;
 pub mod components;
-mod fonts;
-mod presets;
+pub mod fonts;
+mod history;
 pub mod styles;
-mod types;
 mod utils;
 
-use crate::components::preset_list::{preset_container, preset_list};
+use crate::components::backup_list::backup_container;
+use crate::components::bank_check_view::bank_check_view;
+use crate::components::bank_diff_view::bank_diff_view;
+use crate::components::compare_view::compare_view;
+use crate::components::details_panel::{details_panel, DetailsTab};
+use crate::components::dialog::dialog;
+use crate::components::duplicates_view::duplicates_view;
+use crate::components::error_dialog::error_dialog;
+use crate::components::metadata_fields::MetadataField;
+use crate::components::parameter_inspector::ParameterDraft;
+use crate::components::preset_image::GridState;
+use crate::components::preset_list::{
+    bank_tools, grid_rows, grid_scroll_id, preset_container, row_span, search_bar, search_input_id,
+};
+use crate::components::sort_bar::{sort_bar, SortOptions};
+use crate::components::toolbar::toolbar;
 use crate::fonts::load_fonts;
-use crate::presets::{load_presets, save_presets};
+use crate::history::{Edit, EditHistory, EditKind};
 use crate::styles::button_style::GeneralButtonStyle;
-use crate::types::{LoadedPresetEntry, PresetData, PresetEntry, PresetType, Side};
 use iced::alignment::Horizontal;
+use iced::keyboard::key::Named;
+use iced::keyboard::{self, Key, Modifiers};
+use iced::{event, mouse, Event};
 use iced::theme::{Button, Palette, Text};
-use iced::widget::{button, canvas, column, container, row, text, Canvas, Space};
+use iced::widget::scrollable::{AbsoluteOffset, RelativeOffset, Viewport};
+use iced::widget::{button, column, container, row, scrollable, text, text_input, Space};
 use iced::{
     executor, window, Alignment, Application, Color, Command, Element, Length, Settings, Size,
-    Theme,
+    Subscription, Theme,
+};
+use preset_manager::presets::backup::{backup_bank, list_backups, BankBackup};
+use preset_manager::presets::bank_diff::{diff_banks, BankDifference};
+use preset_manager::presets::duplicates::{find_duplicates, DuplicateGroup, PresetLocation};
+use preset_manager::presets::error::BankError;
+use preset_manager::presets::validate::{check_bank, repair_bank, BankCheck, Repair};
+use preset_manager::presets::operations::{BlockMove, OccupiedSlots};
+use preset_manager::presets::search::{matching_presets, PresetQuery};
+use preset_manager::presets::sort::sorted_presets;
+use preset_manager::presets::{
+    load_presets, open_presets, operations, preset_filename_location, save_presets,
+    SYNTH_IMAGE_COLUMNS,
 };
-use nom::Parser;
+use preset_manager::types::{LoadedPresetEntry, PresetData, PresetType, Side};
 use rfd::FileDialog;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use tap::Pipe;
+use std::process::ExitCode;
 use crate::colors::{BACKGROUND_COLOR, PRIMARY_COLOR};
 
-fn main() {
+fn main() -> ExitCode {
+    if cli::is_command_line() {
+        attach_console();
+        return cli::run();
+    }
+
     MainWindow::run(Settings {
         window: window::Settings {
-            size: Size::new(880.0, 600.0),
+            size: Size::new(1180.0, 640.0),
             min_size: Some(Size::new(880.0, 400.0)),
             resizable: true,
             decorations: true,
+            // Closing is handled in `AppMessage::CloseWindow` to ask about unsaved changes first
+            exit_on_close_request: false,
             ..Default::default()
         },
         ..Settings::default()
     })
     .unwrap();
+
+    ExitCode::SUCCESS
+}
+
+/// The binary uses the windows subsystem so the editor does not open a console, attach to the
+/// console of the shell instead so command line output is visible
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
 }
 
+#[cfg(not(windows))]
+fn attach_console() {}
+
 #[derive(Debug, Clone)]
 enum BottomMessage {
     Success(String),
@@ -52,14 +104,143 @@ enum BottomMessage {
 pub enum AppMessage {
     FontsLoaded,
     ClickPreset(i32, Side),
+    PressPreset(i32, Side),
+    ReleasePreset(i32, Side),
+    HoverPreset(i32, Side),
+    UnhoverPreset(i32, Side),
+    MouseReleased,
+    GridScrolled(Side, Viewport),
     Save(Side),
     Close(Side),
     LoadPreset(Side, PresetType),
+    ShowBackups(Side),
+    HideBackups(Side),
+    RestoreBackup(Side, String),
+    Undo(Side),
+    Redo(Side),
+    KeyPressed(Key, Modifiers),
+    ModifiersChanged(Modifiers),
+    SetCopyMode(bool),
+    SetOccupiedSlots(OccupiedSlots),
+    DeletePresets(Side, Vec<i32>),
+    InsertSlot(Side, i32),
+    RemoveSlot(Side, i32),
+    CompactBank(Side),
+    StartSort(Side),
+    UpdateSort(Side, SortOptions),
+    ApplySort(Side),
+    CancelSort(Side),
+    EditMetadata(Side, i32, MetadataField, String),
+    SetParameter(Side, i32, String, serde_json::Value),
+    // Text typed into a number field, applied once it is a valid value
+    EditParameterText(Side, i32, String, String),
+    ShowDetailsTab(DetailsTab),
+    Search(Side, String),
+    NextMatch(Side),
+    PreviousMatch(Side),
+    ComparePresets,
+    CloseComparison,
+    ShowBankDiff,
+    CloseBankDiff,
+    FindDuplicates,
+    AddDuplicatesFolder,
+    // Keeps one preset of the duplicates with the given content hash and deletes the others
+    KeepDuplicate(u64, PresetLocation),
+    CloseDuplicates,
+    CheckBank(Side),
+    ToggleRepair(Repair, bool),
+    RepairBank,
+    CloseBankCheck,
+    CloseError,
+    ConfirmDelete,
+    CancelDelete,
+    CloseWindow,
+    ResolveUnsaved(UnsavedChoice),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UnsavedChoice {
+    Save,
+    Discard,
+    Cancel,
+}
+
+/// An action that discards a side and is waiting for the user to decide about its unsaved changes
+#[derive(Debug, Clone, Copy)]
+enum PendingAction {
+    Close(Side),
+    Load(Side, PresetType),
+    Quit,
+}
+
+/// The duplicates found in the open banks and in extra folders
+struct DuplicatesReport {
+    // Banks searched besides the open sides, they are only read
+    folders: Vec<PresetData>,
+    groups: Vec<DuplicateGroup>,
+}
+
+/// The part of a grid that is scrolled into view, known once it has been scrolled
+#[derive(Debug, Clone, Copy)]
+struct GridViewport {
+    offset: f32,
+    height: f32,
+}
+
+/// A sort shown on a side before it is applied
+struct SortPreview {
+    options: SortOptions,
+    // The slots being sorted, the selection when the sort was started or the whole bank
+    slots: Vec<i32>,
+    presets: HashMap<i32, LoadedPresetEntry>,
+}
+
+/// The slots matching the search on a side, with the query and presets they were found in
+struct SearchResults {
+    query: String,
+    presets: HashMap<i32, LoadedPresetEntry>,
+    matches: Vec<i32>,
 }
 
 struct MainWindow {
     preset_lists: HashMap<Side, PresetData>,
-    selected: Option<(i32, Side)>,
+    selected: HashSet<(i32, Side)>,
+    // The slot a shift-click selects up to
+    selection_anchor: Option<(i32, Side)>,
+    // The slot the mouse button was pressed on, a drag when it holds a preset
+    pressed: Option<(i32, Side)>,
+    hovered: Option<(i32, Side)>,
+    // The slot the arrow keys move from and Enter acts on
+    cursor: Option<(i32, Side)>,
+    grid_viewports: HashMap<Side, GridViewport>,
+    // The presets of each side as they are on disk, to tell whether there are unsaved changes
+    saved_presets: HashMap<Side, HashMap<i32, LoadedPresetEntry>>,
+    pending_action: Option<PendingAction>,
+    pending_delete: Option<(Side, Vec<i32>)>,
+    backups: HashMap<Side, Vec<BankBackup>>,
+    sort_previews: HashMap<Side, SortPreview>,
+    // The text in the search box of each side
+    searches: HashMap<Side, String>,
+    // Kept between messages since matching parses the settings of every preset
+    search_results: HashMap<Side, SearchResults>,
+    details_tab: DetailsTab,
+    parameter_draft: Option<ParameterDraft>,
+    // The two presets being compared, in the order they are shown
+    comparison: Option<[(i32, Side); 2]>,
+    // How the left bank differs from the right one, while the differences are shown
+    bank_diff: Option<Vec<BankDifference>>,
+    duplicates: Option<DuplicatesReport>,
+    // The check of a bank folder with the repairs chosen for it, and the side it is opened on
+    bank_check: Option<(Side, BankCheck, HashSet<Repair>)>,
+    // A failed load or save shown in detail, with what was being done
+    error_details: Option<(String, BankError)>,
+    history: EditHistory,
+    // The side last worked on, keyboard shortcuts apply to it
+    active_side: Side,
+    // Placing a selected preset copies it instead of moving it while this is on or Alt is held
+    copy_mode: bool,
+    occupied_slots: OccupiedSlots,
+    modifiers: Modifiers,
     bottom_message: BottomMessage,
 }
 
@@ -73,7 +254,31 @@ impl Application for MainWindow {
         (
             MainWindow {
                 preset_lists: HashMap::new(),
-                selected: None,
+                selected: HashSet::new(),
+                selection_anchor: None,
+                pressed: None,
+                hovered: None,
+                cursor: None,
+                grid_viewports: HashMap::new(),
+                saved_presets: HashMap::new(),
+                pending_action: None,
+                pending_delete: None,
+                backups: HashMap::new(),
+                sort_previews: HashMap::new(),
+                searches: HashMap::new(),
+                search_results: HashMap::new(),
+                details_tab: DetailsTab::default(),
+                parameter_draft: None,
+                comparison: None,
+                bank_diff: None,
+                duplicates: None,
+                bank_check: None,
+                error_details: None,
+                history: EditHistory::default(),
+                active_side: Side::Left,
+                copy_mode: false,
+                occupied_slots: OccupiedSlots::default(),
+                modifiers: Modifiers::default(),
                 bottom_message: BottomMessage::None,
             },
             load_fonts().map(|_| AppMessage::FontsLoaded),
@@ -84,76 +289,29 @@ impl Application for MainWindow {
         String::from("Preset manager")
     }
 
-    fn update(&mut self, _message: Self::Message) -> Command<Self::Message> {
-        self.bottom_message = BottomMessage::None;
+    fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
+        let command = self.handle_message(message);
+        self.refresh_search_matches();
+        command
+    }
 
-        match _message {
-            AppMessage::FontsLoaded => Command::none(),
-            AppMessage::ClickPreset(i, side) => {
-                match self.selected {
-                    Some((from, from_side)) => {
-                        match self.move_preset(from, from_side, i, side) {
-                            Ok(_) => {},
-                            Err(message) => self.show_error(&message)
-                        }
-                    }
-                    None if !self.preset_lists.contains_key(&side) => {
-                        self.show_error("Side not loaded, cannot select preset");
-                    }
-                    None if self.preset_lists[&side].presets.contains_key(&i) => {
-                        self.selected = Some((i, side));
-                    }
-                    None => (),
-                }
-                Command::none()
-            }
-            AppMessage::Save(side) => {
-                let Some(preset_data) = self.preset_lists.get(&side) else {
-                    self.show_error("Cannot save side, side not loaded");
-                    return Command::none();
-                };
-                match save_presets(
-                    &preset_data.path,
-                    &preset_data.presets,
-                    preset_data.preset_type,
-                ) {
-                    Ok(()) => self.show_success(&format!("Preset {} saved!", &preset_data.name)),
-                    Err(e) => {
-                        self.show_error(&format!("Failed to save preset. {}", e.to_string()));
-                    }
+    fn subscription(&self) -> Subscription<Self::Message> {
+        Subscription::batch([
+            keyboard::on_key_press(|key, modifiers| Some(AppMessage::KeyPressed(key, modifiers))),
+            event::listen_with(|event, status| match event {
+                Event::Window(_, window::Event::CloseRequested) => Some(AppMessage::CloseWindow),
+                Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                    Some(AppMessage::ModifiersChanged(modifiers))
                 }
-                Command::none()
-            }
-            AppMessage::Close(side) => {
-                self.preset_lists.remove(&side);
-                Command::none()
-            }
-            AppMessage::LoadPreset(side, preset_type) => {
-                let Some(preset_path) = FileDialog::new().pick_folder() else {
-                    return Command::none();
-                };
-                if self
-                    .preset_lists
-                    .get(&side.other())
-                    .map(|s| s.preset_type != preset_type)
-                    .unwrap_or(false)
+                // Released outside of any slot, which ends a drag without dropping
+                Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                    if status == event::Status::Ignored =>
                 {
-                    self.show_error("Both sides have the same type of preset (timbre or system)");
-                    return Command::none();
+                    Some(AppMessage::MouseReleased)
                 }
-
-                match load_presets(preset_path.to_str().unwrap(), preset_type, side) {
-                    Ok(preset_data) => {
-                        self.preset_lists.insert(side, preset_data);
-                    }
-                    Err(e) => {
-                        self.show_error(&format!("Cannot load preset. {}", e.to_string()));
-                    }
-                }
-
-                Command::none()
-            }
-        }
+                _ => None,
+            }),
+        ])
     }
 
     fn theme(&self) -> Self::Theme {
@@ -169,12 +327,46 @@ impl Application for MainWindow {
         )
     }
 
-    fn view(&self) -> Element<Self::Message> {
+    fn view(&self) -> Element<'_, Self::Message> {
         use BottomMessage::*;
+        if let Some((title, error)) = &self.error_details {
+            return error_dialog(title, error);
+        }
+        if let Some(action) = self.pending_action {
+            return self.unsaved_changes_dialog(action);
+        }
+        if let Some((side, indices)) = &self.pending_delete {
+            return self.delete_dialog(*side, indices);
+        }
+        if let Some(view) = self.comparison.and_then(|slots| self.comparison_view(slots)) {
+            return view;
+        }
+        if let Some(report) = &self.duplicates {
+            return duplicates_view(&self.duplicate_banks(), &report.groups);
+        }
+        if let Some((_, check, chosen)) = &self.bank_check {
+            return bank_check_view(check, chosen);
+        }
+        if let (Some(differences), Some(left), Some(right)) = (
+            &self.bank_diff,
+            self.preset_lists.get(&Side::Left),
+            self.preset_lists.get(&Side::Right),
+        ) {
+            return bank_diff_view(differences, left, right);
+        }
+
         column([
+            toolbar(
+                self.copy_mode,
+                self.occupied_slots,
+                self.selected.len() == 2,
+                self.can_diff_banks(),
+                !self.preset_lists.is_empty(),
+            ),
             row([
                 self.preset_list_view(Side::Left),
                 self.preset_list_view(Side::Right),
+                self.details_view(),
             ])
             .height(Length::Fill)
             .into(),
@@ -193,21 +385,836 @@ impl Application for MainWindow {
 }
 
 impl MainWindow {
-    fn preset_list_view(&self, side: Side) -> Element<AppMessage> {
-        self.preset_lists.get(&side).map_or_else(
-            || self.preset_not_loaded_view(side),
-            |p| preset_container(&p.name, &p.presets, self.selected, side),
-        )
+    fn handle_message(&mut self, _message: AppMessage) -> Command<AppMessage> {
+        if self.blocked_by_sort_preview(&_message) {
+            return Command::none();
+        }
+        // Key presses only clear the message when they trigger an action
+        match _message {
+            AppMessage::KeyPressed(key, modifiers) => return self.handle_key_press(key, modifiers),
+            AppMessage::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+                return Command::none();
+            }
+            // Pointer movement should not clear the message either
+            AppMessage::HoverPreset(i, side) => {
+                self.hovered = Some((i, side));
+                return Command::none();
+            }
+            AppMessage::UnhoverPreset(i, side) => {
+                // Entering the next slot can be handled before leaving the previous one
+                if self.hovered == Some((i, side)) {
+                    self.hovered = None;
+                }
+                return Command::none();
+            }
+            AppMessage::MouseReleased => {
+                self.pressed = None;
+                return Command::none();
+            }
+            AppMessage::GridScrolled(side, viewport) => {
+                let grid_viewport = GridViewport {
+                    offset: viewport.absolute_offset().y,
+                    height: viewport.bounds().height,
+                };
+                self.grid_viewports.insert(side, grid_viewport);
+                return Command::none();
+            }
+            _ => (),
+        }
+        self.bottom_message = BottomMessage::None;
+
+        match _message {
+            AppMessage::FontsLoaded => Command::none(),
+            AppMessage::ClickPreset(i, side) => {
+                self.active_side = side;
+                self.cursor = Some((i, side));
+                if !self.preset_lists.contains_key(&side) {
+                    self.show_error("Side not loaded, cannot select preset");
+                    return Command::none();
+                }
+
+                let anchor_side = self.selection_anchor.map(|(_, s)| s);
+                if self.modifiers.shift() && anchor_side == Some(side) {
+                    self.select_range(i, side);
+                } else if self.modifiers.command() || self.modifiers.shift() {
+                    self.toggle_selected(i, side);
+                } else if self.selected.is_empty() {
+                    if self.is_occupied(i, side) {
+                        self.selected.insert((i, side));
+                        self.selection_anchor = Some((i, side));
+                    }
+                } else if self.selected.len() > 1 && self.selected.contains(&(i, side)) {
+                    self.clear_selection();
+                } else {
+                    // Place the selection with its top left corner on the clicked slot
+                    let (top, left) = self.selection_corner();
+                    let row_offset = i / SYNTH_IMAGE_COLUMNS - top;
+                    let column_offset = i % SYNTH_IMAGE_COLUMNS - left;
+                    self.place_and_update_selection(row_offset, column_offset, side);
+                }
+                Command::none()
+            }
+            AppMessage::PressPreset(i, side) => {
+                self.pressed = Some((i, side));
+                Command::none()
+            }
+            AppMessage::ReleasePreset(i, side) => {
+                let Some((from, from_side)) = self.pressed.take() else {
+                    return Command::none();
+                };
+                if (from, from_side) == (i, side) {
+                    return self.update(AppMessage::ClickPreset(i, side));
+                }
+                if !self.is_occupied(from, from_side) {
+                    return Command::none();
+                }
+
+                // Dropping a dragged preset, same as selecting it and clicking the target. Dragging
+                // one of several selected presets takes the others along.
+                self.active_side = side;
+                if !self.selected.contains(&(from, from_side)) {
+                    self.selected = HashSet::from([(from, from_side)]);
+                }
+                let row_offset = i / SYNTH_IMAGE_COLUMNS - from / SYNTH_IMAGE_COLUMNS;
+                let column_offset = i % SYNTH_IMAGE_COLUMNS - from % SYNTH_IMAGE_COLUMNS;
+                self.place_and_update_selection(row_offset, column_offset, side);
+                Command::none()
+            }
+            AppMessage::Save(side) => {
+                self.save_side(side);
+                Command::none()
+            }
+            AppMessage::Close(side) => {
+                if self.is_dirty(side) {
+                    self.pending_action = Some(PendingAction::Close(side));
+                } else {
+                    self.close_side(side);
+                }
+                Command::none()
+            }
+            AppMessage::LoadPreset(side, preset_type) => {
+                if self.is_dirty(side) {
+                    self.pending_action = Some(PendingAction::Load(side, preset_type));
+                    return Command::none();
+                }
+                self.load_side(side, preset_type);
+                Command::none()
+            }
+            AppMessage::CloseWindow => {
+                if [Side::Left, Side::Right].iter().any(|s| self.is_dirty(*s)) {
+                    self.pending_action = Some(PendingAction::Quit);
+                    return Command::none();
+                }
+                window::close(window::Id::MAIN)
+            }
+            AppMessage::ResolveUnsaved(choice) => {
+                let Some(action) = self.pending_action.take() else {
+                    return Command::none();
+                };
+                let sides = match action {
+                    PendingAction::Close(side) | PendingAction::Load(side, _) => vec![side],
+                    PendingAction::Quit => vec![Side::Left, Side::Right],
+                };
+
+                match choice {
+                    UnsavedChoice::Cancel => return Command::none(),
+                    UnsavedChoice::Save => {
+                        for side in sides {
+                            // Keep everything open if saving fails so nothing is lost
+                            if self.is_dirty(side) && !self.save_side(side) {
+                                return Command::none();
+                            }
+                        }
+                    }
+                    UnsavedChoice::Discard => (),
+                }
+
+                match action {
+                    PendingAction::Close(side) => {
+                        self.close_side(side);
+                        Command::none()
+                    }
+                    PendingAction::Load(side, preset_type) => {
+                        self.load_side(side, preset_type);
+                        Command::none()
+                    }
+                    PendingAction::Quit => window::close(window::Id::MAIN),
+                }
+            }
+            AppMessage::ShowBackups(side) => {
+                let Some(preset_data) = self.preset_lists.get(&side) else {
+                    self.show_error("Cannot show backups, side not loaded");
+                    return Command::none();
+                };
+                match list_backups(&preset_data.path, preset_data.preset_type) {
+                    Ok(backups) => {
+                        self.backups.insert(side, backups);
+                    }
+                    Err(e) => self.show_bank_error("Cannot list backups", e),
+                }
+                Command::none()
+            }
+            AppMessage::HideBackups(side) => {
+                self.backups.remove(&side);
+                Command::none()
+            }
+            AppMessage::RestoreBackup(side, backup_path) => {
+                let Some(preset_data) = self.preset_lists.get(&side) else {
+                    self.show_error("Cannot restore backup, side not loaded");
+                    return Command::none();
+                };
+                let backup = match load_presets(&backup_path, preset_data.preset_type, side) {
+                    Ok(backup) => backup,
+                    Err(e) => {
+                        self.show_bank_error("Cannot restore backup", e);
+                        return Command::none();
+                    }
+                };
+
+                // Keep the bank location so the next save writes the backup over the bank
+                let slots = preset_data
+                    .presets
+                    .keys()
+                    .chain(backup.presets.keys())
+                    .map(|i| (side, *i))
+                    .collect::<Vec<_>>();
+                let restore = Edit::record(
+                    EditKind::RestoreBackup,
+                    &mut self.preset_lists,
+                    slots,
+                    |lists| {
+                        lists.get_mut(&side).unwrap().presets = backup.presets;
+                        Ok(())
+                    },
+                );
+                if let Ok(((), edit)) = restore {
+                    self.history.push(edit, &mut self.preset_lists);
+                }
+                self.show_success(&format!(
+                    "Backup of {} restored, save to write it to the bank",
+                    &self.preset_lists[&side].name
+                ));
+                self.backups.remove(&side);
+                self.clear_selection();
+                self.refresh_sort_previews();
+                Command::none()
+            }
+            AppMessage::Undo(side) => {
+                self.active_side = side;
+                self.clear_selection();
+                match self.history.undo(side, &mut self.preset_lists) {
+                    Ok(kind) => self.show_success(&format!("Undid {}", kind.label())),
+                    Err(message) => self.show_error(&message),
+                }
+                self.refresh_sort_previews();
+                Command::none()
+            }
+            AppMessage::Redo(side) => {
+                self.active_side = side;
+                self.clear_selection();
+                match self.history.redo(side, &mut self.preset_lists) {
+                    Ok(kind) => self.show_success(&format!("Redid {}", kind.label())),
+                    Err(message) => self.show_error(&message),
+                }
+                self.refresh_sort_previews();
+                Command::none()
+            }
+            AppMessage::DeletePresets(side, indices) => {
+                let Some(preset_data) = self.preset_lists.get(&side) else {
+                    self.show_error("Side not loaded, cannot delete presets");
+                    return Command::none();
+                };
+                let indices: Vec<i32> = indices
+                    .into_iter()
+                    .filter(|i| preset_data.presets.contains_key(i))
+                    .collect();
+                if !indices.is_empty() {
+                    self.active_side = side;
+                    self.pending_delete = Some((side, indices));
+                }
+                Command::none()
+            }
+            AppMessage::ConfirmDelete => {
+                let Some((side, indices)) = self.pending_delete.take() else {
+                    return Command::none();
+                };
+                let result = Edit::record(
+                    EditKind::Delete,
+                    &mut self.preset_lists,
+                    indices.iter().map(|i| (side, *i)),
+                    |lists| operations::delete_presets(lists, side, &indices),
+                );
+                match result {
+                    Ok((deleted, edit)) => {
+                        self.history.push(edit, &mut self.preset_lists);
+                        self.clear_selection();
+                        self.show_success(&format!(
+                            "Deleted {} {}, save to remove the files",
+                            deleted.len(),
+                            if deleted.len() == 1 { "preset" } else { "presets" }
+                        ));
+                    }
+                    Err(message) => self.show_error(&message),
+                }
+                Command::none()
+            }
+            AppMessage::InsertSlot(side, index) => {
+                self.active_side = side;
+                let result = Edit::record_sides(
+                    EditKind::InsertSlot,
+                    &mut self.preset_lists,
+                    &[side],
+                    |lists| operations::insert_slot(lists, side, index),
+                );
+                match result {
+                    Ok(((), edit)) if edit.is_empty() => {
+                        self.show_success("Nothing to do, there are no presets from that slot on")
+                    }
+                    Ok(((), edit)) => {
+                        self.history.push(edit, &mut self.preset_lists);
+                        self.shift_selection(side, |i| if i >= index { i + 1 } else { i });
+                        self.show_success(&format!(
+                            "Inserted an empty slot at {}",
+                            preset_filename_location(index)
+                        ));
+                    }
+                    Err(message) => self.show_error(&message),
+                }
+                Command::none()
+            }
+            AppMessage::RemoveSlot(side, index) => {
+                self.active_side = side;
+                let result = Edit::record_sides(
+                    EditKind::RemoveSlot,
+                    &mut self.preset_lists,
+                    &[side],
+                    |lists| operations::remove_slot(lists, side, index),
+                );
+                match result {
+                    Ok(((), edit)) if edit.is_empty() => {
+                        self.show_success("Nothing to do, there are no presets after that slot")
+                    }
+                    Ok(((), edit)) => {
+                        self.history.push(edit, &mut self.preset_lists);
+                        self.shift_selection(side, |i| if i > index { i - 1 } else { i });
+                        self.show_success(&format!(
+                            "Removed slot {}",
+                            preset_filename_location(index)
+                        ));
+                    }
+                    Err(message) => self.show_error(&message),
+                }
+                Command::none()
+            }
+            AppMessage::CompactBank(side) => {
+                self.active_side = side;
+                let result = Edit::record_sides(
+                    EditKind::Compact,
+                    &mut self.preset_lists,
+                    &[side],
+                    |lists| operations::compact_bank(lists, side),
+                );
+                match result {
+                    Ok((0, _)) => self.show_success("The bank has no gaps"),
+                    Ok((moved, edit)) => {
+                        self.history.push(edit, &mut self.preset_lists);
+                        self.selected.retain(|(_, s)| *s != side);
+                        self.show_success(&format!(
+                            "Closed the gaps, {moved} {} moved up",
+                            if moved == 1 { "preset" } else { "presets" }
+                        ));
+                    }
+                    Err(message) => self.show_error(&message),
+                }
+                Command::none()
+            }
+            AppMessage::StartSort(side) => {
+                if self.sort_previews.remove(&side).is_some() {
+                    return Command::none();
+                }
+                let Some(preset_data) = self.preset_lists.get(&side) else {
+                    self.show_error("Side not loaded, cannot sort it");
+                    return Command::none();
+                };
+                let selected: Vec<i32> = self
+                    .selected
+                    .iter()
+                    .filter(|(_, s)| *s == side)
+                    .map(|(i, _)| *i)
+                    .collect();
+                let slots = if selected.len() > 1 {
+                    selected
+                } else {
+                    preset_data.presets.keys().copied().collect()
+                };
+
+                self.active_side = side;
+                self.selected.retain(|(_, s)| *s != side);
+                self.hovered = None;
+                self.sort_previews.insert(
+                    side,
+                    SortPreview {
+                        options: SortOptions::default(),
+                        slots,
+                        presets: HashMap::new(),
+                    },
+                );
+                self.refresh_sort_previews();
+                Command::none()
+            }
+            AppMessage::UpdateSort(side, options) => {
+                if let Some(preview) = self.sort_previews.get_mut(&side) {
+                    preview.options = options;
+                }
+                self.refresh_sort_previews();
+                Command::none()
+            }
+            AppMessage::ApplySort(side) => {
+                let Some(preview) = self.sort_previews.remove(&side) else {
+                    return Command::none();
+                };
+                let key = preview.options.key();
+                let result = Edit::record_sides(
+                    EditKind::Sort,
+                    &mut self.preset_lists,
+                    &[side],
+                    |lists| {
+                        operations::sort_presets(
+                            lists,
+                            side,
+                            &preview.slots,
+                            &key,
+                            preview.options.descending,
+                        )
+                    },
+                );
+                match result {
+                    Ok(((), edit)) if edit.is_empty() => {
+                        self.show_success("Nothing to do, the presets are already in that order")
+                    }
+                    Ok(((), edit)) => {
+                        self.history.push(edit, &mut self.preset_lists);
+                        self.show_success(&format!("Sorted by {key}"));
+                    }
+                    Err(message) => self.show_error(&message),
+                }
+                Command::none()
+            }
+            AppMessage::CancelSort(side) => {
+                self.sort_previews.remove(&side);
+                Command::none()
+            }
+            AppMessage::EditMetadata(side, index, field, value) => {
+                let result = Edit::record(
+                    EditKind::Metadata,
+                    &mut self.preset_lists,
+                    [(side, index)],
+                    |lists| {
+                        let entry = lists
+                            .get_mut(&side)
+                            .and_then(|p| p.presets.get_mut(&index))
+                            .ok_or("The preset no longer exists")?;
+                        field.set(&mut entry.metadata, value);
+                        Ok(())
+                    },
+                );
+                match result {
+                    Ok(((), edit)) => {
+                        let merge_key = format!("{side:?} {index} {field:?}");
+                        let edit = edit.merging(merge_key);
+                        self.history.push(edit, &mut self.preset_lists);
+                    }
+                    Err(message) => self.show_error(&message),
+                }
+                Command::none()
+            }
+            AppMessage::SetParameter(side, index, path, value) => {
+                self.parameter_draft = None;
+                if let Err(message) = self.set_parameter(side, index, &path, &value) {
+                    self.show_error(&message);
+                }
+                Command::none()
+            }
+            AppMessage::EditParameterText(side, index, path, text) => {
+                let value = text
+                    .trim()
+                    .parse::<serde_json::Number>()
+                    .map_err(|_| format!("{} is not a number", text.trim()))
+                    .and_then(|number| {
+                        self.set_parameter(side, index, &path, &serde_json::Value::Number(number))
+                    });
+                self.parameter_draft = Some(ParameterDraft {
+                    side,
+                    index,
+                    path,
+                    text,
+                    error: value.err(),
+                });
+                Command::none()
+            }
+            AppMessage::ComparePresets => {
+                let mut selected: Vec<(i32, Side)> = self.selected.iter().copied().collect();
+                selected.sort_by_key(|(index, side)| (*side as u8, *index));
+                match selected[..] {
+                    [first, second] => self.comparison = Some([first, second]),
+                    _ => self.show_error("Select two presets to compare"),
+                }
+                Command::none()
+            }
+            AppMessage::CloseComparison => {
+                self.comparison = None;
+                Command::none()
+            }
+            AppMessage::ShowBankDiff => {
+                if let (Some(left), Some(right)) = (
+                    self.preset_lists.get(&Side::Left),
+                    self.preset_lists.get(&Side::Right),
+                ) {
+                    self.bank_diff = Some(diff_banks(&left.presets, &right.presets));
+                }
+                Command::none()
+            }
+            AppMessage::CloseBankDiff => {
+                self.bank_diff = None;
+                Command::none()
+            }
+            AppMessage::FindDuplicates => {
+                self.duplicates = Some(DuplicatesReport {
+                    folders: Vec::new(),
+                    groups: Vec::new(),
+                });
+                self.refresh_duplicates();
+                Command::none()
+            }
+            AppMessage::AddDuplicatesFolder => {
+                self.add_duplicates_folder();
+                Command::none()
+            }
+            AppMessage::KeepDuplicate(hash, keep) => {
+                self.keep_duplicate(hash, keep);
+                Command::none()
+            }
+            AppMessage::CloseDuplicates => {
+                self.duplicates = None;
+                Command::none()
+            }
+            AppMessage::CheckBank(side) => {
+                if self.is_dirty(side) {
+                    self.show_error("Save or discard the changes before checking the bank");
+                } else if let Some(preset_data) = self.preset_lists.get(&side) {
+                    let check = check_bank(&preset_data.path, preset_data.preset_type);
+                    self.show_bank_check(side, check);
+                }
+                Command::none()
+            }
+            AppMessage::ToggleRepair(repair, on) => {
+                if let Some((_, _, chosen)) = &mut self.bank_check {
+                    if on {
+                        chosen.insert(repair);
+                    } else {
+                        chosen.remove(&repair);
+                    }
+                }
+                Command::none()
+            }
+            AppMessage::RepairBank => {
+                self.repair_checked_bank();
+                Command::none()
+            }
+            AppMessage::CloseBankCheck => {
+                self.bank_check = None;
+                Command::none()
+            }
+            AppMessage::CloseError => {
+                self.error_details = None;
+                Command::none()
+            }
+            AppMessage::Search(side, query) => {
+                self.searches.insert(side, query);
+                Command::none()
+            }
+            AppMessage::NextMatch(side) => self.select_match(side, true),
+            AppMessage::PreviousMatch(side) => self.select_match(side, false),
+            AppMessage::ShowDetailsTab(tab) => {
+                self.details_tab = tab;
+                Command::none()
+            }
+            AppMessage::CancelDelete => {
+                self.pending_delete = None;
+                Command::none()
+            }
+            AppMessage::SetCopyMode(copy_mode) => {
+                self.copy_mode = copy_mode;
+                Command::none()
+            }
+            AppMessage::SetOccupiedSlots(occupied_slots) => {
+                self.occupied_slots = occupied_slots;
+                Command::none()
+            }
+            AppMessage::KeyPressed(_, _)
+            | AppMessage::ModifiersChanged(_)
+            | AppMessage::HoverPreset(_, _)
+            | AppMessage::UnhoverPreset(_, _)
+            | AppMessage::MouseReleased
+            | AppMessage::GridScrolled(_, _) => Command::none(),
+        }
+    }
+
+    fn preset_list_view(&self, side: Side) -> Element<'_, AppMessage> {
+        let Some(p) = self.preset_lists.get(&side) else {
+            return self.preset_not_loaded_view(side);
+        };
+        if let Some(backups) = self.backups.get(&side) {
+            return backup_container(&p.name, backups, side);
+        }
+
+        let preview = self.sort_previews.get(&side);
+        let presets = preview.map_or(&p.presets, |preview| &preview.presets);
+        let matches: Option<HashSet<i32>> = self
+            .search_matches(side)
+            .map(|matches| matches.iter().copied().collect());
+        let grid = GridState {
+            matches: matches.as_ref(),
+            ..self.grid_state()
+        };
+        let search = search_bar(
+            side,
+            self.searches.get(&side).map_or("", String::as_str),
+            matches.as_ref().map(HashSet::len),
+        );
+        let tools = match preview {
+            Some(preview) => sort_bar(&preview.options, side, preview.slots.len()),
+            None => bank_tools(
+                grid,
+                side,
+                self.history.can_undo(side),
+                self.history.can_redo(side),
+            ),
+        };
+
+        preset_container(&p.name, presets, grid, side, self.is_dirty(side), search, tools)
+    }
+
+    /// The slots on a side matching its search, `None` when nothing is searched for
+    fn search_matches(&self, side: Side) -> Option<&[i32]> {
+        self.search_results.get(&side).map(|results| results.matches.as_slice())
+    }
+
+    /// Matches the search of each side again when its query or the presets shown changed
+    fn refresh_search_matches(&mut self) {
+        for side in [Side::Left, Side::Right] {
+            let query = self.searches.get(&side).and_then(|query| PresetQuery::parse(query));
+            let presets = self.preset_lists.get(&side).map(|p| {
+                self.sort_previews
+                    .get(&side)
+                    .map_or(&p.presets, |preview| &preview.presets)
+            });
+            let (Some(query), Some(presets)) = (query, presets) else {
+                self.search_results.remove(&side);
+                continue;
+            };
+            let text = &self.searches[&side];
+            let unchanged = self
+                .search_results
+                .get(&side)
+                .is_some_and(|results| results.query == *text && results.presets == *presets);
+            if !unchanged {
+                let results = SearchResults {
+                    query: text.clone(),
+                    presets: presets.clone(),
+                    matches: matching_presets(presets, &query),
+                };
+                self.search_results.insert(side, results);
+            }
+        }
+    }
+
+    /// Selects the match after, or before, the first selected preset on a side
+    fn select_match(&mut self, side: Side, forward: bool) -> Command<AppMessage> {
+        if !self.preset_lists.contains_key(&side) {
+            return Command::none();
+        }
+        let matches = self.search_matches(side).unwrap_or_default();
+        let current = self
+            .selected
+            .iter()
+            .filter(|(_, s)| *s == side)
+            .map(|(i, _)| *i)
+            .min();
+
+        // Wraps around at either end
+        let next = match (current, forward) {
+            (Some(current), true) => matches.iter().find(|i| **i > current).or(matches.first()),
+            (Some(current), false) => {
+                matches.iter().rev().find(|i| **i < current).or(matches.last())
+            }
+            (None, true) => matches.first(),
+            (None, false) => matches.last(),
+        };
+        match next {
+            Some(index) => {
+                let index = *index;
+                self.active_side = side;
+                self.cursor = Some((index, side));
+                self.selected = HashSet::from([(index, side)]);
+                self.selection_anchor = Some((index, side));
+                self.reveal_slot(index, side)
+            }
+            None => {
+                self.show_error("No preset matches the search");
+                Command::none()
+            }
+        }
+    }
+
+    /// Scrolls the grid of a side just far enough to bring the row of `index` into view, if it
+    /// is not in view already
+    fn reveal_slot(&mut self, index: i32, side: Side) -> Command<AppMessage> {
+        let Some(p) = self.preset_lists.get(&side) else {
+            return Command::none();
+        };
+        let (top, bottom) = row_span(index / SYNTH_IMAGE_COLUMNS);
+        let Some(viewport) = self.grid_viewports.get_mut(&side) else {
+            // Until the grid is scrolled its height is unknown. Scrolling to the same fraction of
+            // the grid as the row keeps the row visible, however high the grid is.
+            let rows = grid_rows(&p.presets);
+            let y = if rows > 1 {
+                (index / SYNTH_IMAGE_COLUMNS) as f32 / (rows - 1) as f32
+            } else {
+                0.0
+            };
+            let offset = RelativeOffset { x: 0.0, y: y.min(1.0) };
+            return scrollable::snap_to(grid_scroll_id(side), offset);
+        };
+
+        let offset = if top < viewport.offset {
+            top
+        } else if bottom > viewport.offset + viewport.height {
+            bottom - viewport.height
+        } else {
+            return Command::none();
+        };
+        // Scrolling from code does not report the new position, so it is tracked here
+        viewport.offset = offset;
+        scrollable::scroll_to(grid_scroll_id(side), AbsoluteOffset { x: 0.0, y: offset })
     }
 
-    fn preset_not_loaded_view(&self, side: Side) -> Element<AppMessage> {
+    /// Moves the keyboard cursor on the active side, starting from the selection or the first slot
+    fn move_cursor(&mut self, row_offset: i32, column_offset: i32) -> Command<AppMessage> {
+        let side = self.active_side;
+        let Some(p) = self.preset_lists.get(&side) else {
+            return Command::none();
+        };
+        let start = self
+            .cursor
+            .filter(|(_, s)| *s == side)
+            .map(|(i, _)| i)
+            .or_else(|| self.selected.iter().filter(|(_, s)| *s == side).map(|(i, _)| *i).min());
+        let index = match start {
+            Some(start) => {
+                let last = grid_rows(&p.presets) * SYNTH_IMAGE_COLUMNS - 1;
+                (start + row_offset * SYNTH_IMAGE_COLUMNS + column_offset).clamp(0, last)
+            }
+            None => 0,
+        };
+
+        self.cursor = Some((index, side));
+        self.reveal_slot(index, side)
+    }
+
+    /// The type of bank a new bank on `side` has to be, to match the other side
+    fn load_type(&self, side: Side, system: bool) -> PresetType {
+        match self.preset_lists.get(&side.other()) {
+            Some(other) => other.preset_type,
+            None if system => PresetType::System,
+            None => PresetType::Timbre,
+        }
+    }
+
+    /// A dialog or report is shown in place of the grids
+    fn showing_dialog(&self) -> bool {
+        self.error_details.is_some()
+            || self.pending_action.is_some()
+            || self.pending_delete.is_some()
+            || self.comparison.is_some()
+            || self.bank_diff.is_some()
+            || self.duplicates.is_some()
+            || self.bank_check.is_some()
+    }
+
+    /// Escape closes whatever dialog is shown, other keys are ignored while one is
+    fn close_dialog(&mut self) -> Command<AppMessage> {
+        if self.error_details.take().is_some() {
+            return Command::none();
+        }
+        if self.pending_action.is_some() {
+            return self.update(AppMessage::ResolveUnsaved(UnsavedChoice::Cancel));
+        }
+        self.pending_delete = None;
+        self.comparison = None;
+        self.bank_diff = None;
+        self.duplicates = None;
+        self.bank_check = None;
+        Command::none()
+    }
+
+    /// Changes a setting of a preset as one undoable edit, merged with the edits that directly
+    /// follow it on the same setting
+    fn set_parameter(
+        &mut self,
+        side: Side,
+        index: i32,
+        path: &str,
+        value: &serde_json::Value,
+    ) -> Result<(), String> {
+        let ((), edit) = Edit::record(
+            EditKind::Parameter,
+            &mut self.preset_lists,
+            [(side, index)],
+            |lists| operations::set_preset_parameter(lists, side, index, path, value),
+        )?;
+        let edit = edit.merging(format!("{side:?} {index} {path}"));
+        self.history.push(edit, &mut self.preset_lists);
+        Ok(())
+    }
+
+    /// The details of the selected preset, when exactly one is selected
+    fn details_view(&self) -> Element<'_, AppMessage> {
+        let selected = match self.selected.iter().collect::<Vec<_>>()[..] {
+            [(index, side)] => self
+                .preset_lists
+                .get(side)
+                .and_then(|p| p.presets.get(index))
+                .map(|entry| (*index, *side, entry)),
+            _ => None,
+        };
+
+        match selected {
+            Some((index, side, entry)) => {
+                let saved = self.saved_presets.get(&side).and_then(|p| p.get(&index));
+                let draft = self.parameter_draft.as_ref();
+                details_panel(self.details_tab, side, index, entry, saved, draft)
+            }
+            None => Space::with_width(0).into(),
+        }
+    }
+
+    fn preset_not_loaded_view(&self, side: Side) -> Element<'_, AppMessage> {
         let other_side = side.other();
         let other_side_preset_type = self.preset_lists
             .get(&other_side)
             .map(|s| s.preset_type);
 
+        let hint = if side == self.active_side {
+            "Ctrl+O to load a timbre bank, Ctrl+Shift+O for a system bank"
+        } else {
+            "Tab to switch to this side"
+        };
+
         column([
             text("No preset loaded").into(),
+            text(hint).size(14).into(),
             Space::with_height(10).into(),
             if matches!(other_side_preset_type, None | Some(PresetType::Timbre)) {
                 button("Load timbre preset")
@@ -236,61 +1243,646 @@ impl MainWindow {
         .into()
     }
 
+    fn unsaved_changes_dialog(&self, action: PendingAction) -> Element<'_, AppMessage> {
+        let names = match action {
+            PendingAction::Close(side) | PendingAction::Load(side, _) => self
+                .preset_lists
+                .get(&side)
+                .map(|p| p.name.clone())
+                .unwrap_or_default(),
+            PendingAction::Quit => [Side::Left, Side::Right]
+                .iter()
+                .filter(|s| self.is_dirty(**s))
+                .map(|s| self.preset_lists[s].name.clone())
+                .collect::<Vec<_>>()
+                .join(" and "),
+        };
+
+        dialog(
+            "Unsaved changes",
+            &format!("{names} has unsaved changes. Save them before continuing?"),
+            vec![
+                ("Save", AppMessage::ResolveUnsaved(UnsavedChoice::Save)),
+                ("Discard", AppMessage::ResolveUnsaved(UnsavedChoice::Discard)),
+                ("Cancel", AppMessage::ResolveUnsaved(UnsavedChoice::Cancel)),
+            ],
+        )
+    }
+
+    fn delete_dialog(&self, side: Side, indices: &[i32]) -> Element<'_, AppMessage> {
+        let slots = indices
+            .iter()
+            .map(|i| preset_filename_location(*i))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let name = self
+            .preset_lists
+            .get(&side)
+            .map(|p| p.name.as_str())
+            .unwrap_or_default();
+
+        dialog(
+            "Delete presets",
+            &format!(
+                "Delete the presets in {} {slots} of {name}? This can be undone until you save.",
+                if indices.len() == 1 { "slot" } else { "slots" }
+            ),
+            vec![
+                ("Delete", AppMessage::ConfirmDelete),
+                ("Cancel", AppMessage::CancelDelete),
+            ],
+        )
+    }
+
+    /// The banks searched for duplicates with the side they are open on, the open sides first
+    fn duplicate_banks(&self) -> Vec<(Option<Side>, &PresetData)> {
+        let sides = [Side::Left, Side::Right]
+            .into_iter()
+            .filter_map(|side| Some((Some(side), self.preset_lists.get(&side)?)));
+        let folders = self
+            .duplicates
+            .iter()
+            .flat_map(|report| &report.folders)
+            .map(|preset_data| (None, preset_data));
+
+        sides.chain(folders).collect()
+    }
+
+    fn refresh_duplicates(&mut self) {
+        let banks: Vec<&PresetData> = self.duplicate_banks().into_iter().map(|(_, p)| p).collect();
+        let groups = find_duplicates(&banks);
+        if let Some(report) = &mut self.duplicates {
+            report.groups = groups;
+        }
+    }
+
+    fn add_duplicates_folder(&mut self) {
+        let Some(preset_type) = self.preset_lists.values().next().map(|p| p.preset_type) else {
+            return;
+        };
+        let Some(path) = FileDialog::new().pick_folder() else {
+            return;
+        };
+        let canonical = fs::canonicalize(&path).ok();
+        let searched = self
+            .duplicate_banks()
+            .into_iter()
+            .any(|(_, p)| canonical.is_some() && fs::canonicalize(&p.path).ok() == canonical);
+        if searched {
+            self.show_error("That folder is already searched for duplicates");
+            return;
+        }
+        // Loaded as the left side, only the presets themselves are used
+        match load_presets(&path.to_string_lossy(), preset_type, Side::Left) {
+            Ok(preset_data) => {
+                if let Some(report) = &mut self.duplicates {
+                    report.folders.push(preset_data);
+                }
+                self.refresh_duplicates();
+            }
+            Err(e) => self.show_bank_error("Cannot load folder", e),
+        }
+    }
+
+    /// Deletes every copy of a duplicated preset from the open banks except `keep`, as one edit
+    fn keep_duplicate(&mut self, hash: u64, keep: PresetLocation) {
+        let banks = self.duplicate_banks();
+        let Some(group) = self
+            .duplicates
+            .as_ref()
+            .and_then(|report| report.groups.iter().find(|g| g.hash == hash))
+        else {
+            return;
+        };
+        let (to_delete, elsewhere): (Vec<_>, Vec<_>) = group
+            .presets
+            .iter()
+            .filter(|location| **location != keep)
+            .map(|location| (banks[location.bank].0, location.index))
+            .partition(|(side, _)| side.is_some());
+        let to_delete: Vec<(i32, Side)> = to_delete
+            .into_iter()
+            .filter_map(|(side, index)| Some((index, side?)))
+            .collect();
+
+        let result = Edit::record(
+            EditKind::Delete,
+            &mut self.preset_lists,
+            to_delete.iter().map(|(index, side)| (*side, *index)),
+            |lists| {
+                for side in [Side::Left, Side::Right] {
+                    let indices: Vec<i32> = to_delete
+                        .iter()
+                        .filter(|(_, s)| *s == side)
+                        .map(|(i, _)| *i)
+                        .collect();
+                    if !indices.is_empty() {
+                        operations::delete_presets(lists, side, &indices)?;
+                    }
+                }
+                Ok(())
+            },
+        );
+        match result {
+            Ok(((), edit)) => {
+                self.history.push(edit, &mut self.preset_lists);
+                self.selected.retain(|slot| !to_delete.contains(slot));
+                self.refresh_sort_previews();
+                self.refresh_duplicates();
+                let mut message = format!(
+                    "Deleted {} {}, save to remove the files",
+                    to_delete.len(),
+                    if to_delete.len() == 1 { "copy" } else { "copies" }
+                );
+                if !elsewhere.is_empty() {
+                    message.push_str(". The copies in other folders were left alone");
+                }
+                self.show_success(&message);
+            }
+            Err(message) => self.show_error(&message),
+        }
+    }
+
+    /// Banks can be compared when both sides hold one of the same type
+    fn can_diff_banks(&self) -> bool {
+        match (self.preset_lists.get(&Side::Left), self.preset_lists.get(&Side::Right)) {
+            (Some(left), Some(right)) => left.preset_type == right.preset_type,
+            _ => false,
+        }
+    }
+
+    fn comparison_view(&self, slots: [(i32, Side); 2]) -> Option<Element<'_, AppMessage>> {
+        let [first, second] = slots.map(|(index, side)| {
+            let entry = self.preset_lists.get(&side)?.presets.get(&index)?;
+            Some((side, index, entry))
+        });
+
+        Some(compare_view(first?, second?))
+    }
+
+    fn grid_state(&self) -> GridState<'_> {
+        GridState {
+            selected: &self.selected,
+            hovered: self.hovered,
+            dragged: self.pressed.filter(|(i, side)| self.is_occupied(*i, *side)),
+            matches: None,
+            cursor: self.cursor,
+            active_side: self.active_side,
+        }
+    }
+
+    fn is_occupied(&self, index: i32, side: Side) -> bool {
+        self.preset_lists
+            .get(&side)
+            .is_some_and(|p| p.presets.contains_key(&index))
+    }
+
+    fn is_dirty(&self, side: Side) -> bool {
+        match (self.preset_lists.get(&side), self.saved_presets.get(&side)) {
+            (Some(preset_data), Some(saved)) => preset_data.presets != *saved,
+            _ => false,
+        }
+    }
+
+    /// Backs up and saves a side, returns whether that succeeded
+    fn save_side(&mut self, side: Side) -> bool {
+        let Some(preset_data) = self.preset_lists.get(&side) else {
+            self.show_error("Cannot save side, side not loaded");
+            return false;
+        };
+        // Nothing is backed up or rewritten for a bank that is the same as on disk
+        if !self.is_dirty(side) {
+            self.show_success(&format!("Nothing to save, {} is unchanged", preset_data.name));
+            return true;
+        }
+        if let Err(e) = backup_bank(&preset_data.path, preset_data.preset_type) {
+            self.show_bank_error("Failed to back up preset, nothing was saved", e);
+            return false;
+        }
+        match save_presets(
+            &preset_data.path,
+            &preset_data.presets,
+            preset_data.preset_type,
+        ) {
+            Ok(()) => {
+                self.saved_presets.insert(side, preset_data.presets.clone());
+                self.show_success(&format!("Preset {} saved!", &self.preset_lists[&side].name));
+                true
+            }
+            Err(e) => {
+                self.show_bank_error("Failed to save preset", e);
+                false
+            }
+        }
+    }
+
+    fn close_side(&mut self, side: Side) {
+        self.preset_lists.remove(&side);
+        self.saved_presets.remove(&side);
+        self.backups.remove(&side);
+        self.sort_previews.remove(&side);
+        self.searches.remove(&side);
+        self.history.clear_side(side);
+        self.selected.retain(|(_, s)| *s != side);
+        if matches!(self.selection_anchor, Some((_, s)) if s == side) {
+            self.selection_anchor = None;
+        }
+        if matches!(self.cursor, Some((_, s)) if s == side) {
+            self.cursor = None;
+        }
+    }
+
+    fn load_side(&mut self, side: Side, preset_type: PresetType) {
+        let Some(preset_path) = FileDialog::new().pick_folder() else {
+            return;
+        };
+        if self
+            .preset_lists
+            .get(&side.other())
+            .map(|s| s.preset_type != preset_type)
+            .unwrap_or(false)
+        {
+            self.show_error("Both sides have the same type of preset (timbre or system)");
+            return;
+        }
+
+        let path = preset_path.to_str().unwrap();
+        match open_presets(path, preset_type, side) {
+            Ok(preset_data) => self.open_bank(side, preset_data),
+            Err(e) => {
+                self.show_bank_error("Cannot load preset", e);
+                // Offer to repair the folder when checking it tells what is wrong
+                let check = check_bank(path, preset_type);
+                if !check.is_ok() {
+                    self.show_bank_check(side, check);
+                }
+            }
+        }
+    }
+
+    fn open_bank(&mut self, side: Side, preset_data: PresetData) {
+        let broken = preset_data.presets.values().filter(|p| p.broken.is_some()).count();
+        match broken {
+            0 => {}
+            1 => self.show_error("1 preset could not be loaded, hover it to see why"),
+            count => self.show_error(&format!(
+                "{count} presets could not be loaded, hover them to see why"
+            )),
+        }
+        if let Some(error) = &preset_data.metadata_error {
+            self.show_error(&format!(
+                "Opened without metadata, it is kept as it is until metadata is added. {error}"
+            ));
+        }
+        if !preset_data.displaced.is_empty() {
+            self.show_error(&format!(
+                "{} share a slot with an earlier preset and are not shown, saving leaves them \
+                 out. Check the bank to renumber them",
+                preset_data.displaced.join(", ")
+            ));
+        }
+        self.close_side(side);
+        self.saved_presets.insert(side, preset_data.presets.clone());
+        self.preset_lists.insert(side, preset_data);
+        self.active_side = side;
+    }
+
+    /// Shows the problems of a bank with every repair that applies to them chosen
+    fn show_bank_check(&mut self, side: Side, check: BankCheck) {
+        let chosen = check.applicable_repairs().into_iter().collect();
+        self.bank_check = Some((side, check, chosen));
+    }
+
+    /// Repairs the checked bank and opens it on its side
+    fn repair_checked_bank(&mut self) {
+        let Some((side, check, chosen)) = self.bank_check.take() else {
+            return;
+        };
+        let repairs: Vec<Repair> = Repair::ALL
+            .into_iter()
+            .filter(|repair| chosen.contains(repair))
+            .collect();
+        let summary = match repair_bank(&check.path, check.preset_type, &repairs) {
+            Ok(summary) => summary,
+            Err(e) => {
+                self.show_error(&format!("Cannot repair {}. {e:#}", check.path));
+                self.bank_check = Some((side, check, chosen));
+                return;
+            }
+        };
+        match load_presets(&check.path, check.preset_type, side) {
+            Ok(preset_data) => {
+                self.open_bank(side, preset_data);
+                self.show_success(&format!("Repaired {}: {summary}", check.path));
+            }
+            Err(e) => self.show_bank_error("Repaired, but cannot load the bank", e),
+        }
+    }
+
     fn show_error(&mut self, message: &str) {
         self.bottom_message = BottomMessage::Error(message.to_string())
     }
 
+    /// Shows a failed load or save in the error dialog, and its summary below the grids once the
+    /// dialog is closed
+    fn show_bank_error(&mut self, title: &str, error: BankError) {
+        self.show_error(&format!("{title}. {error}"));
+        self.error_details = Some((title.to_string(), error));
+    }
+
     fn show_success(&mut self, message: &str) {
         self.bottom_message = BottomMessage::Success(message.to_string())
     }
 
-    fn move_preset(&mut self, from: i32, from_side: Side, to: i32, to_side: Side) -> Result<(), String> {
-        if !self.preset_lists.contains_key(&from_side)
-            || !self.preset_lists.contains_key(&to_side)
-        {
-            return Err("Either side is not loaded, cannot proceed with move".to_string());
+    /// Grid edits on a side are put on hold while a sort is previewed there, since the grid shows
+    /// the preview instead of the bank
+    fn blocked_by_sort_preview(&mut self, message: &AppMessage) -> bool {
+        let side = match message {
+            AppMessage::ClickPreset(_, side)
+            | AppMessage::PressPreset(_, side)
+            | AppMessage::ReleasePreset(_, side)
+            | AppMessage::HoverPreset(_, side)
+            | AppMessage::DeletePresets(side, _)
+            | AppMessage::InsertSlot(side, _)
+            | AppMessage::RemoveSlot(side, _)
+            | AppMessage::CompactBank(side) => *side,
+            _ => return false,
+        };
+        if !self.sort_previews.contains_key(&side) {
+            return false;
         }
 
-        let from_preset = self
-            .preset_lists
-            .get_mut(&from_side)
-            .unwrap()
-            .presets
-            .remove(&from);
-        let to_preset = self
-            .preset_lists
-            .get_mut(&to_side)
-            .unwrap()
+        if !matches!(message, AppMessage::HoverPreset(_, _)) {
+            self.show_error("Apply or cancel the sort first");
+        }
+        true
+    }
+
+    /// Recomputes the previewed sorts from the current banks
+    fn refresh_sort_previews(&mut self) {
+        for (side, preview) in self.sort_previews.iter_mut() {
+            let Some(preset_data) = self.preset_lists.get(side) else {
+                continue;
+            };
+            preview.presets = sorted_presets(
+                &preset_data.presets,
+                &preview.slots,
+                &preview.options.key(),
+                preview.options.descending,
+            );
+        }
+    }
+
+    fn clear_selection(&mut self) {
+        self.selected.clear();
+        self.selection_anchor = None;
+        self.parameter_draft = None;
+    }
+
+    fn toggle_selected(&mut self, index: i32, side: Side) {
+        if !self.selected.remove(&(index, side)) && self.is_occupied(index, side) {
+            self.selected.insert((index, side));
+        }
+        self.selection_anchor = Some((index, side));
+        self.show_selection_count();
+    }
+
+    /// Selects every preset between the anchor and the given slot, in reading order
+    fn select_range(&mut self, index: i32, side: Side) {
+        let Some((anchor, _)) = self.selection_anchor else {
+            return;
+        };
+        let (first, last) = (anchor.min(index), anchor.max(index));
+        self.selected = self.preset_lists[&side]
             .presets
-            .remove(&to);
+            .keys()
+            .filter(|i| (first..=last).contains(*i))
+            .map(|i| (*i, side))
+            .collect();
+        self.show_selection_count();
+    }
 
-        match from_preset {
-            Some(e) => {
-                self.preset_lists
-                    .get_mut(&to_side)
-                    .unwrap()
-                    .presets
-                    .insert(to, e);
-            }
-            None => {
-                return Err("Selected item does not exist, cannot move".to_string());
-            }
+    fn show_selection_count(&mut self) {
+        if self.selected.len() > 1 {
+            self.show_success(&format!(
+                "{} presets selected, click a slot to move them there or shift them with Ctrl+arrows",
+                self.selected.len()
+            ));
         }
+    }
 
-        // Swap the items if there is a preset in both slots
-        match to_preset {
-            Some(e) => {
-                self.preset_lists
-                    .get_mut(&from_side)
-                    .unwrap()
-                    .presets
-                    .insert(from, e);
+    /// Renumbers the selected presets of a side after its slots were shifted
+    fn shift_selection(&mut self, side: Side, new_index: impl Fn(i32) -> i32) {
+        self.selected = self
+            .selected
+            .iter()
+            .map(|(i, s)| if *s == side { (new_index(*i), *s) } else { (*i, *s) })
+            .collect();
+        self.selection_anchor = None;
+    }
+
+    /// The side all selected presets are on, with their indices in order
+    fn selection_block(&self) -> Result<(Side, Vec<i32>), String> {
+        let Some((_, side)) = self.selected.iter().next().copied() else {
+            return Err("No presets selected".to_string());
+        };
+        if self.selected.iter().any(|(_, s)| *s != side) {
+            return Err(
+                "The selection spans both sides, select presets on one side only".to_string(),
+            );
+        }
+        let mut indices: Vec<i32> = self.selected.iter().map(|(i, _)| *i).collect();
+        indices.sort();
+
+        Ok((side, indices))
+    }
+
+    /// The topmost row and leftmost column of the selection
+    fn selection_corner(&self) -> (i32, i32) {
+        let top = self.selected.iter().map(|(i, _)| i / SYNTH_IMAGE_COLUMNS).min().unwrap_or(0);
+        let left = self.selected.iter().map(|(i, _)| i % SYNTH_IMAGE_COLUMNS).min().unwrap_or(0);
+        (top, left)
+    }
+
+    /// Places the selection for a click or drop. A moved block stays selected on its new slots so
+    /// it can be placed again, a single preset or a copy is deselected.
+    fn place_and_update_selection(&mut self, row_offset: i32, column_offset: i32, to_side: Side) {
+        let copy = self.copy_mode || self.modifiers.alt();
+        match self.place_selection(row_offset, column_offset, to_side, copy) {
+            Ok(targets) if targets.len() > 1 && !copy => {
+                self.selected = targets.into_iter().map(|i| (i, to_side)).collect();
+                self.selection_anchor = None;
             }
-            None => (),
+            Ok(_) => self.clear_selection(),
+            Err(message) => self.show_error(&message),
+        }
+    }
+
+    /// Moves or copies the selected presets by the given number of rows and columns onto a side.
+    /// Returns the slots they were placed in.
+    fn place_selection(
+        &mut self,
+        row_offset: i32,
+        column_offset: i32,
+        to_side: Side,
+        copy: bool,
+    ) -> Result<Vec<i32>, String> {
+        let (from_side, sources) = self.selection_block()?;
+        if self.sort_previews.contains_key(&from_side) || self.sort_previews.contains_key(&to_side) {
+            return Err("Apply or cancel the sort first".to_string());
+        }
+        let occupied = self.occupied_slots;
+        if from_side == to_side && row_offset == 0 && column_offset == 0 {
+            return Ok(sources);
         }
 
-        self.selected = None;
+        let target_taken = sources.iter().any(|i| {
+            let target = i + row_offset * SYNTH_IMAGE_COLUMNS + column_offset;
+            let part_of_block = from_side == to_side && sources.contains(&target);
+            self.is_occupied(target, to_side) && !part_of_block
+        });
+        let kind = match (copy, target_taken && occupied == OccupiedSlots::Swap) {
+            (true, _) => EditKind::Copy,
+            (false, true) => EditKind::Swap,
+            (false, false) => EditKind::Move,
+        };
+        let sides = if from_side == to_side {
+            vec![from_side]
+        } else {
+            vec![from_side, to_side]
+        };
+        let block_move = BlockMove {
+            from_side,
+            sources,
+            to_side,
+            row_offset,
+            column_offset,
+            occupied,
+            copy,
+        };
+        let (targets, edit) = Edit::record_sides(kind, &mut self.preset_lists, &sides, |lists| {
+            operations::move_block(lists, &block_move)
+        })?;
+        self.history.push(edit, &mut self.preset_lists);
 
-        Ok(())
+        Ok(targets)
+    }
+
+    fn handle_key_press(&mut self, key: Key, modifiers: Modifiers) -> Command<AppMessage> {
+        if self.showing_dialog() {
+            return match key {
+                Key::Named(Named::Escape) => self.close_dialog(),
+                _ => Command::none(),
+            };
+        }
+        let c = match key.as_ref() {
+            Key::Character(c) => c,
+            Key::Named(named) => return self.handle_named_key_press(named, modifiers),
+            Key::Unidentified => return Command::none(),
+        };
+
+        match c.to_lowercase().as_str() {
+            "z" if modifiers.command() && modifiers.shift() => {
+                self.update(AppMessage::Redo(self.active_side))
+            }
+            "z" if modifiers.command() => self.update(AppMessage::Undo(self.active_side)),
+            "y" if modifiers.command() => self.update(AppMessage::Redo(self.active_side)),
+            "f" if modifiers.command() => text_input::focus(search_input_id(self.active_side)),
+            "s" if modifiers.command() => self.update(AppMessage::Save(self.active_side)),
+            "w" if modifiers.command() => self.update(AppMessage::Close(self.active_side)),
+            "o" if modifiers.command() => {
+                let preset_type = self.load_type(self.active_side, modifiers.shift());
+                self.update(AppMessage::LoadPreset(self.active_side, preset_type))
+            }
+            "a" if modifiers.command() => {
+                if let Some(preset_data) = self.preset_lists.get(&self.active_side) {
+                    let side = self.active_side;
+                    self.selected = preset_data.presets.keys().map(|i| (*i, side)).collect();
+                    self.selection_anchor = None;
+                }
+                Command::none()
+            }
+            _ => Command::none(),
+        }
+    }
+
+    fn handle_named_key_press(&mut self, key: Named, modifiers: Modifiers) -> Command<AppMessage> {
+        match key {
+            Named::Delete | Named::Backspace if !self.selected.is_empty() => {
+                match self.selection_block() {
+                    Ok((side, indices)) => self.update(AppMessage::DeletePresets(side, indices)),
+                    Err(message) => {
+                        self.show_error(&message);
+                        Command::none()
+                    }
+                }
+            }
+            Named::Insert => {
+                // The slot under the pointer, or the first selected preset
+                let slot = self.hovered.or_else(|| {
+                    let side = self.active_side;
+                    let first = self.selected.iter().filter(|(_, s)| *s == side).map(|(i, _)| *i);
+                    first.min().map(|i| (i, side))
+                });
+                match slot {
+                    Some((i, side)) => self.update(AppMessage::InsertSlot(side, i)),
+                    None => Command::none(),
+                }
+            }
+            Named::F3 if modifiers.shift() => {
+                self.update(AppMessage::PreviousMatch(self.active_side))
+            }
+            Named::F3 => self.update(AppMessage::NextMatch(self.active_side)),
+            Named::Escape => {
+                self.clear_selection();
+                Command::none()
+            }
+            Named::ArrowUp | Named::ArrowDown | Named::ArrowLeft | Named::ArrowRight
+                if modifiers.command() && !self.selected.is_empty() =>
+            {
+                let (row_offset, column_offset) = match key {
+                    Named::ArrowUp => (-1, 0),
+                    Named::ArrowDown => (1, 0),
+                    Named::ArrowLeft => (0, -1),
+                    _ => (0, 1),
+                };
+                // Shifting always moves, the selection follows the presets
+                self.bottom_message = BottomMessage::None;
+                let side = self.selected.iter().next().map(|(_, s)| *s).unwrap();
+                match self.place_selection(row_offset, column_offset, side, false) {
+                    Ok(targets) => {
+                        let shift = row_offset * SYNTH_IMAGE_COLUMNS + column_offset;
+                        self.cursor = self
+                            .cursor
+                            .filter(|(_, s)| *s == side)
+                            .map(|(i, _)| (i + shift, side));
+                        self.selected = targets.into_iter().map(|i| (i, side)).collect();
+                    }
+                    Err(message) => self.show_error(&message),
+                }
+                Command::none()
+            }
+            Named::ArrowUp => self.move_cursor(-1, 0),
+            Named::ArrowDown => self.move_cursor(1, 0),
+            Named::ArrowLeft => self.move_cursor(0, -1),
+            Named::ArrowRight => self.move_cursor(0, 1),
+            Named::Tab => {
+                if !self.preset_lists.contains_key(&self.active_side.other()) {
+                    self.show_error("The other side is empty, open a bank there first");
+                    return Command::none();
+                }
+                self.active_side = self.active_side.other();
+                // The cursor keeps its slot on the other side
+                self.cursor = self.cursor.map(|(i, _)| (i, self.active_side));
+                Command::none()
+            }
+            Named::Enter => match self.cursor {
+                Some((i, side)) if side == self.active_side => {
+                    self.update(AppMessage::ClickPreset(i, side))
+                }
+                _ => self.move_cursor(0, 0),
+            },
+            _ => Command::none(),
+        }
     }
 }
@@ -0,0 +1,113 @@
+use crate::presets::error::BankError;
+use crate::presets::staging::is_bank_file;
+use crate::presets::{load_preset_list, SYSTEM_PRESET_LIST_FILE, TIMBRE_PRESET_LIST_FILE};
+use crate::types::PresetType;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use std::fs;
+use std::path::Path;
+
+const BACKUP_DIR: &str = ".backups";
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+
+#[derive(Debug, Clone)]
+pub struct BankBackup {
+    pub path: String,
+    pub created: DateTime<Local>,
+    pub preset_count: Option<usize>,
+}
+
+fn preset_list_file(preset_type: PresetType) -> &'static str {
+    match preset_type {
+        PresetType::Timbre => TIMBRE_PRESET_LIST_FILE,
+        PresetType::System => SYSTEM_PRESET_LIST_FILE,
+    }
+}
+
+/// Copies the current bank files of `path` into `.backups/<timestamp>/` inside the bank folder.
+/// Returns `None` when there is no bank of this type in the folder yet.
+pub fn backup_bank(path: &str, preset_type: PresetType) -> Result<Option<String>, BankError> {
+    if !Path::new(path).join(preset_list_file(preset_type)).exists() {
+        return Ok(None);
+    }
+
+    let backups = Path::new(path).join(BACKUP_DIR);
+    let timestamp = Local::now().format(BACKUP_TIMESTAMP_FORMAT).to_string();
+    // Timbre and system banks can share a snapshot folder since their files never overlap, but two
+    // saves of the same bank within one second each need their own folder
+    let mut backup = backups.join(&timestamp);
+    let mut counter = 1;
+    while backup.join(preset_list_file(preset_type)).exists() {
+        backup = backups.join(format!("{timestamp}_{counter}"));
+        counter += 1;
+    }
+    fs::create_dir_all(&backup).map_err(|e| BankError::io("create", &backup, e))?;
+
+    let read_error = |e| BankError::io("read", path, e);
+    for entry in fs::read_dir(path).map_err(read_error)? {
+        let entry = entry.map_err(read_error)?;
+        let file_name = match entry.file_name().into_string() {
+            Ok(file_name) => file_name,
+            Err(_) => continue,
+        };
+
+        let is_file = entry.file_type().map_err(|e| BankError::read(entry.path(), e))?.is_file();
+        if is_bank_file(&file_name, preset_type) && is_file {
+            fs::copy(entry.path(), backup.join(&file_name))
+                .map_err(|e| BankError::io("back up", entry.path(), e))?;
+        }
+    }
+
+    Ok(Some(backup.to_string_lossy().to_string()))
+}
+
+/// Lists the backups of a bank, newest first. Only the preset list of each backup is read, the
+/// presets themselves are loaded when a backup is restored.
+pub fn list_backups(path: &str, preset_type: PresetType) -> Result<Vec<BankBackup>, BankError> {
+    let backups = Path::new(path).join(BACKUP_DIR);
+    if !backups.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut result = Vec::new();
+    let read_error = |e| BankError::io("read", &backups, e);
+    for entry in fs::read_dir(&backups).map_err(read_error)? {
+        let entry = entry.map_err(read_error)?;
+        if !entry.path().join(preset_list_file(preset_type)).exists() {
+            continue;
+        }
+        let Some((created, counter)) = entry.file_name().to_str().and_then(parse_backup_name)
+        else {
+            continue;
+        };
+        let preset_count = load_preset_list(entry.path().join(preset_list_file(preset_type)))
+            .ok()
+            .map(|(presets, _)| presets.len());
+
+        result.push((
+            counter,
+            BankBackup {
+                path: entry.path().to_string_lossy().to_string(),
+                created,
+                preset_count,
+            },
+        ));
+    }
+    result.sort_by(|(a_counter, a), (b_counter, b)| {
+        b.created.cmp(&a.created).then_with(|| b_counter.cmp(a_counter))
+    });
+
+    Ok(result.into_iter().map(|(_, backup)| backup).collect())
+}
+
+/// The time a backup folder was made and its counter, which is 0 for the first backup in a second
+fn parse_backup_name(dir_name: &str) -> Option<(DateTime<Local>, u32)> {
+    let timestamp = dir_name.get(..19)?;
+    let naive = NaiveDateTime::parse_from_str(timestamp, BACKUP_TIMESTAMP_FORMAT).ok()?;
+    let created = Local.from_local_datetime(&naive).earliest()?;
+    let counter = match &dir_name[19..] {
+        "" => 0,
+        suffix => suffix.strip_prefix('_')?.parse().ok()?,
+    };
+
+    Some((created, counter))
+}
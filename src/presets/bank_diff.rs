@@ -0,0 +1,166 @@
+use crate::presets::hash::{content_hash, image_hash, settings_hash};
+use crate::types::LoadedPresetEntry;
+use std::collections::{HashMap, HashSet};
+
+/// How a preset of the left bank relates to the presets of the right bank
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BankDifference {
+    /// Same settings and image in the same slot on both sides
+    Identical(i32),
+    /// Same settings and image, in slot `from` on the left and `to` on the right
+    Moved { from: i32, to: i32 },
+    OnlyLeft(i32),
+    OnlyRight(i32),
+    /// Both sides hold a different preset in the slot
+    Differs { index: i32, settings: bool, image: bool },
+}
+
+impl BankDifference {
+    /// The slot the difference is listed under, the left one for moved presets
+    pub fn index(&self) -> i32 {
+        match self {
+            BankDifference::Identical(index)
+            | BankDifference::OnlyLeft(index)
+            | BankDifference::OnlyRight(index)
+            | BankDifference::Differs { index, .. } => *index,
+            BankDifference::Moved { from, .. } => *from,
+        }
+    }
+}
+
+/// Compares two banks slot by slot. Presets whose content is found in another slot of the other
+/// bank count as moved, the rest of the slots held on only one side count as only on that side.
+/// The differences are ordered by slot.
+pub fn diff_banks(
+    left: &HashMap<i32, LoadedPresetEntry>,
+    right: &HashMap<i32, LoadedPresetEntry>,
+) -> Vec<BankDifference> {
+    let left_hashes: HashMap<i32, u64> = left.iter().map(|(i, p)| (*i, content_hash(p))).collect();
+    let right_hashes: HashMap<i32, u64> =
+        right.iter().map(|(i, p)| (*i, content_hash(p))).collect();
+    let mut left_indices: Vec<i32> = left.keys().copied().collect();
+    left_indices.sort();
+    let mut right_indices: Vec<i32> = right.keys().copied().collect();
+    right_indices.sort();
+
+    let mut differences = Vec::new();
+    let mut matched_left = HashSet::new();
+    let mut matched_right = HashSet::new();
+    for index in &left_indices {
+        if right_hashes.get(index) == Some(&left_hashes[index]) {
+            differences.push(BankDifference::Identical(*index));
+            matched_left.insert(*index);
+            matched_right.insert(*index);
+        }
+    }
+    for from in &left_indices {
+        if matched_left.contains(from) {
+            continue;
+        }
+        let to = right_indices
+            .iter()
+            .find(|to| !matched_right.contains(*to) && right_hashes[*to] == left_hashes[from]);
+        if let Some(to) = to {
+            differences.push(BankDifference::Moved { from: *from, to: *to });
+            matched_left.insert(*from);
+            matched_right.insert(*to);
+        }
+    }
+    for index in &left_indices {
+        if matched_left.contains(index) {
+            continue;
+        }
+        match right.get(index).filter(|_| !matched_right.contains(index)) {
+            Some(other) => {
+                differences.push(BankDifference::Differs {
+                    index: *index,
+                    settings: settings_hash(&left[index]) != settings_hash(other),
+                    image: image_hash(&left[index]) != image_hash(other),
+                });
+                matched_right.insert(*index);
+            }
+            None => differences.push(BankDifference::OnlyLeft(*index)),
+        }
+    }
+    differences.extend(
+        right_indices
+            .iter()
+            .filter(|i| !matched_right.contains(*i))
+            .map(|i| BankDifference::OnlyRight(*i)),
+    );
+    differences.sort_by_key(BankDifference::index);
+
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{PointF, Side};
+    use BankDifference::*;
+
+    fn presets(slots: &[(i32, &str)]) -> HashMap<i32, LoadedPresetEntry> {
+        slots
+            .iter()
+            .map(|&(i, settings)| {
+                let entry = LoadedPresetEntry::new(
+                    i,
+                    Side::Left,
+                    String::new(),
+                    String::new(),
+                    Vec::new(),
+                    settings.to_string(),
+                    None,
+                );
+                (i, entry)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn presets_in_the_same_slot_with_the_same_content_are_identical() {
+        let left = presets(&[(0, r#"{"a":1}"#), (1, r#"{"b":1}"#)]);
+        let right = presets(&[(0, r#"{ "a": 1 }"#), (1, r#"{"b":1}"#)]);
+
+        assert_eq!(diff_banks(&left, &right), [Identical(0), Identical(1)]);
+    }
+
+    #[test]
+    fn a_preset_found_in_another_slot_is_moved() {
+        let left = presets(&[(0, r#"{"a":1}"#), (2, r#"{"b":1}"#)]);
+        let right = presets(&[(0, r#"{"a":1}"#), (5, r#"{"b":1}"#)]);
+
+        assert_eq!(diff_banks(&left, &right), [Identical(0), Moved { from: 2, to: 5 }]);
+    }
+
+    #[test]
+    fn a_preset_changed_in_place_differs_in_what_changed() {
+        let left = presets(&[(0, r#"{"a":1}"#), (1, r#"{"b":1}"#)]);
+        let mut right = presets(&[(0, r#"{"a":2}"#), (1, r#"{"b":1}"#)]);
+        right.get_mut(&1).unwrap().image = vec![vec![PointF::new(0.5, 0.5)]];
+
+        assert_eq!(
+            diff_banks(&left, &right),
+            [
+                Differs {
+                    index: 0,
+                    settings: true,
+                    image: false
+                },
+                Differs {
+                    index: 1,
+                    settings: false,
+                    image: true
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn presets_without_a_match_are_only_on_their_side() {
+        let left = presets(&[(0, r#"{"a":1}"#)]);
+        let right = presets(&[(3, r#"{"c":1}"#)]);
+
+        assert_eq!(diff_banks(&left, &right), [OnlyLeft(0), OnlyRight(3)]);
+    }
+}
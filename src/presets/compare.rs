@@ -0,0 +1,138 @@
+use crate::presets::parameters::{flatten, Parameter};
+use serde_json::Value;
+
+/// How a parameter differs between two presets
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterChange {
+    Changed { before: Value, after: Value },
+    /// Only in the second preset
+    Added(Value),
+    /// Only in the first preset
+    Removed(Value),
+}
+
+/// A parameter that differs between two presets
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterDiff {
+    pub path: String,
+    pub change: ParameterChange,
+}
+
+fn parameters(preset_data: &str) -> Result<Vec<Parameter>, String> {
+    let settings: Value = serde_json::from_str(preset_data)
+        .map_err(|e| format!("The settings are not valid JSON: {e}"))?;
+    let Value::Object(settings) = settings else {
+        return Err("The settings are not a JSON object".to_string());
+    };
+
+    let mut parameters = Vec::new();
+    for (key, value) in &settings {
+        flatten(key, value, &mut parameters);
+    }
+    Ok(parameters)
+}
+
+/// Lists the parameters that differ between the settings of two presets, in the order of the first
+/// preset followed by the parameters only the second one has
+pub fn compare_presets(before: &str, after: &str) -> Result<Vec<ParameterDiff>, String> {
+    let before = parameters(before)?;
+    let after = parameters(after)?;
+
+    let mut diffs: Vec<ParameterDiff> = before
+        .iter()
+        .filter_map(|b| {
+            let change = match after.iter().find(|a| a.path == b.path) {
+                Some(a) if a.value == b.value => return None,
+                Some(a) => ParameterChange::Changed {
+                    before: b.value.clone(),
+                    after: a.value.clone(),
+                },
+                None => ParameterChange::Removed(b.value.clone()),
+            };
+            Some(ParameterDiff {
+                path: b.path.clone(),
+                change,
+            })
+        })
+        .collect();
+    diffs.extend(
+        after
+            .iter()
+            .filter(|a| !before.iter().any(|b| b.path == a.path))
+            .map(|a| ParameterDiff {
+                path: a.path.clone(),
+                change: ParameterChange::Added(a.value.clone()),
+            }),
+    );
+
+    Ok(diffs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use ParameterChange::*;
+
+    fn diff(path: &str, change: ParameterChange) -> ParameterDiff {
+        ParameterDiff {
+            path: path.to_string(),
+            change,
+        }
+    }
+
+    #[test]
+    fn identical_settings_have_no_differences() {
+        let settings = r#"{"name":"Pad","filter":{"cutoff":0.5}}"#;
+        assert_eq!(compare_presets(settings, settings), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn changed_removed_and_added_parameters_are_listed_in_order() {
+        let before = r#"{"name":"Pad","reverb":0.2,"mono":false}"#;
+        let after = r#"{"name":"Pad","mono":true,"voices":4}"#;
+
+        assert_eq!(
+            compare_presets(before, after),
+            Ok(vec![
+                diff("reverb", Removed(json!(0.2))),
+                diff(
+                    "mono",
+                    Changed {
+                        before: json!(false),
+                        after: json!(true)
+                    }
+                ),
+                diff("voices", Added(json!(4))),
+            ])
+        );
+    }
+
+    #[test]
+    fn nested_objects_are_compared_parameter_by_parameter() {
+        let before = r#"{"filter":{"cutoff":0.5,"env":{"attack":0.1}},"osc":[{"shape":"saw"}]}"#;
+        let after = r#"{"filter":{"cutoff":0.5,"env":{"attack":0.3,"decay":0.2}},"osc":[{}]}"#;
+
+        assert_eq!(
+            compare_presets(before, after),
+            Ok(vec![
+                diff(
+                    "filter.env.attack",
+                    Changed {
+                        before: json!(0.1),
+                        after: json!(0.3)
+                    }
+                ),
+                diff("osc.0.shape", Removed(json!("saw"))),
+                diff("filter.env.decay", Added(json!(0.2))),
+                diff("osc.0", Added(json!({}))),
+            ])
+        );
+    }
+
+    #[test]
+    fn settings_that_are_not_an_object_cannot_be_compared() {
+        assert!(compare_presets("[1]", "{}").is_err());
+        assert!(compare_presets("{}", "{").is_err());
+    }
+}
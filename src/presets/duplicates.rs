@@ -0,0 +1,111 @@
+use crate::presets::hash::content_hash;
+use crate::types::PresetData;
+use std::collections::HashMap;
+
+/// A preset in one of the banks searched for duplicates
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct PresetLocation {
+    /// Position of the bank in the list given to [`find_duplicates`]
+    pub bank: usize,
+    pub index: i32,
+}
+
+/// Presets with the same settings and image, in the order of the banks and then of their slots
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateGroup {
+    pub hash: u64,
+    pub presets: Vec<PresetLocation>,
+}
+
+/// Finds the presets that occur more than once in the given banks, within a bank or across them.
+/// Groups are ordered by where their first preset is.
+pub fn find_duplicates(banks: &[&PresetData]) -> Vec<DuplicateGroup> {
+    let mut groups: Vec<DuplicateGroup> = Vec::new();
+    let mut group_of_hash: HashMap<u64, usize> = HashMap::new();
+    for (bank, preset_data) in banks.iter().enumerate() {
+        let mut indices: Vec<i32> = preset_data.presets.keys().copied().collect();
+        indices.sort();
+        for index in indices {
+            let hash = content_hash(&preset_data.presets[&index]);
+            let location = PresetLocation { bank, index };
+            match group_of_hash.get(&hash) {
+                Some(group) => groups[*group].presets.push(location),
+                None => {
+                    group_of_hash.insert(hash, groups.len());
+                    groups.push(DuplicateGroup {
+                        hash,
+                        presets: vec![location],
+                    });
+                }
+            }
+        }
+    }
+    groups.retain(|group| group.presets.len() > 1);
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{LoadedPresetEntry, PresetType, Side};
+
+    fn bank(settings: &[(i32, &str)]) -> PresetData {
+        let presets = settings
+            .iter()
+            .map(|&(i, settings)| {
+                let entry = LoadedPresetEntry::new(
+                    i,
+                    Side::Left,
+                    String::new(),
+                    String::new(),
+                    Vec::new(),
+                    settings.to_string(),
+                    None,
+                );
+                (i, entry)
+            })
+            .collect();
+
+        PresetData {
+            path: String::new(),
+            preset_type: PresetType::Timbre,
+            presets,
+            name: String::new(),
+            metadata_error: None,
+            displaced: Vec::new(),
+        }
+    }
+
+    fn locations(group: &DuplicateGroup) -> Vec<(usize, i32)> {
+        group.presets.iter().map(|p| (p.bank, p.index)).collect()
+    }
+
+    #[test]
+    fn presets_differing_only_in_formatting_are_duplicates() {
+        let bank = bank(&[
+            (0, r#"{"a":1,"b":2}"#),
+            (3, "{ \"b\": 2,\n  \"a\": 1 }"),
+            (5, r#"{"a":1,"b":3}"#),
+        ]);
+        let groups = find_duplicates(&[&bank]);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(locations(&groups[0]), [(0, 0), (0, 3)]);
+    }
+
+    #[test]
+    fn duplicates_are_found_across_banks_in_bank_and_slot_order() {
+        let first = bank(&[(4, r#"{"a":1}"#), (7, r#"{"b":1}"#)]);
+        let second = bank(&[(0, r#"{"b":1}"#), (2, r#"{"a":1}"#), (9, r#"{"c":1}"#)]);
+        let groups = find_duplicates(&[&first, &second]);
+
+        let groups: Vec<Vec<(usize, i32)>> = groups.iter().map(locations).collect();
+        assert_eq!(groups, [vec![(0, 4), (1, 2)], vec![(0, 7), (1, 0)]]);
+    }
+
+    #[test]
+    fn unique_presets_form_no_group() {
+        assert!(find_duplicates(&[&bank(&[(0, "{}"), (1, "[]")])]).is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::presets::lsi_parser::LsiError;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// What went wrong reading or writing a bank, with the file it happened to
+#[derive(Debug, Error)]
+pub enum BankError {
+    #[error("The preset list {} is missing", .path.display())]
+    MissingList { path: PathBuf },
+    /// A file the preset list refers to does not exist
+    #[error("{} is missing", .path.display())]
+    MissingFile { path: PathBuf },
+    #[error("{} is not valid JSON at line {line}, column {column}: {message}", .path.display())]
+    Json {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    #[error("{} is not a valid image: {error}", .path.display())]
+    Image { path: PathBuf, error: LsiError },
+    #[error("Permission denied to {action} {}", .path.display())]
+    PermissionDenied { path: PathBuf, action: &'static str },
+    #[error("Cannot {action} {}: {error}", .path.display())]
+    Io {
+        path: PathBuf,
+        action: &'static str,
+        error: io::Error,
+    },
+    #[error("Cannot write {} as JSON: {error}", .path.display())]
+    Serialize {
+        path: PathBuf,
+        error: serde_json::Error,
+    },
+    /// The bank is half way through a save that has to be finished before it can be read
+    #[error(
+        "A save of {} was interrupted, open the bank for editing to finish it",
+        .path.display()
+    )]
+    InterruptedSave { path: PathBuf },
+    /// A save that failed part way, the bank files were left as they were
+    #[error("Save aborted, the bank was left unchanged. {0}")]
+    SaveAborted(Box<BankError>),
+}
+
+impl BankError {
+    /// An error doing `action`, e.g. "create", to the file or folder at `path`
+    pub fn io(action: &'static str, path: impl Into<PathBuf>, error: io::Error) -> Self {
+        let path = path.into();
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => BankError::PermissionDenied { path, action },
+            _ => BankError::Io {
+                path,
+                action,
+                error,
+            },
+        }
+    }
+
+    /// An error reading the file at `path`, which is missing when it does not exist
+    pub fn read(path: impl Into<PathBuf>, error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => BankError::MissingFile { path: path.into() },
+            _ => BankError::io("read", path, error),
+        }
+    }
+
+    pub fn json(path: impl Into<PathBuf>, error: serde_json::Error) -> Self {
+        let (line, column) = (error.line(), error.column());
+        // The position is kept in its own fields rather than repeated in the message
+        let message = error.to_string();
+        let message = message
+            .strip_suffix(&format!(" at line {line} column {column}"))
+            .unwrap_or(&message)
+            .to_string();
+
+        BankError::Json {
+            path: path.into(),
+            line,
+            column,
+            message,
+        }
+    }
+
+    /// The file or folder the error happened to
+    pub fn path(&self) -> &Path {
+        match self {
+            BankError::MissingList { path }
+            | BankError::MissingFile { path }
+            | BankError::Json { path, .. }
+            | BankError::Image { path, .. }
+            | BankError::PermissionDenied { path, .. }
+            | BankError::Io { path, .. }
+            | BankError::Serialize { path, .. }
+            | BankError::InterruptedSave { path } => path,
+            BankError::SaveAborted(error) => error.path(),
+        }
+    }
+
+    /// Points the error at the file in `folder` when it happened to the file of the same name in
+    /// `working_dir`, a directory inside the folder that a save works in
+    pub(crate) fn in_folder(mut self, working_dir: &Path, folder: &Path) -> Self {
+        let path = self.path_mut();
+        if let Ok(file) = path.strip_prefix(working_dir) {
+            *path = folder.join(file);
+        }
+        self
+    }
+
+    fn path_mut(&mut self) -> &mut PathBuf {
+        match self {
+            BankError::MissingList { path }
+            | BankError::MissingFile { path }
+            | BankError::Json { path, .. }
+            | BankError::Image { path, .. }
+            | BankError::PermissionDenied { path, .. }
+            | BankError::Io { path, .. }
+            | BankError::Serialize { path, .. }
+            | BankError::InterruptedSave { path } => path,
+            BankError::SaveAborted(error) => error.path_mut(),
+        }
+    }
+
+    /// Labelled facts about the error for showing it in detail, the file first
+    pub fn details(&self) -> Vec<(&'static str, String)> {
+        let mut details = vec![("File", self.path().display().to_string())];
+        match self {
+            BankError::Json { line, column, .. } => {
+                details.push(("Line", line.to_string()));
+                details.push(("Column", column.to_string()));
+            }
+            BankError::Image { error, .. } => {
+                details.push(("Byte offset", error.offset.to_string()));
+            }
+            BankError::Io { error, .. } => details.push(("System error", error.to_string())),
+            BankError::SaveAborted(error) => return error.details(),
+            _ => {}
+        }
+
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_in_a_working_dir_point_at_the_bank_folder() {
+        let folder = Path::new("bank");
+        let staging = folder.join(".preset_manager_staging_timbre");
+        let error = BankError::SaveAborted(Box::new(BankError::MissingFile {
+            path: staging.join("settings_timbre0_1.json"),
+        }));
+
+        let error = error.in_folder(&staging, folder);
+        assert_eq!(error.path(), folder.join("settings_timbre0_1.json"));
+        // Errors outside of the working dir keep their path
+        let error = error.in_folder(&staging, Path::new("other"));
+        assert_eq!(error.path(), folder.join("settings_timbre0_1.json"));
+    }
+}
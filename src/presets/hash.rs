@@ -0,0 +1,142 @@
+use crate::presets::lsi_writer::write_lsi_image;
+use crate::types::{BrokenPreset, LoadedPresetEntry};
+use serde_json::Value;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// 64 bit FNV-1a, which unlike the standard library's hasher gives the same result on every build
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Writes JSON without whitespace and with the keys of every object sorted
+fn write_normalized(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_normalized(&map[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_normalized(item, out);
+            }
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+/// A hash of the settings of a preset that ignores formatting and key order, so it is the same
+/// for presets whose settings only differ in how they are written
+pub fn settings_hash(entry: &LoadedPresetEntry) -> u64 {
+    let normalized = match serde_json::from_str::<Value>(&entry.preset_data) {
+        Ok(settings) => {
+            let mut normalized = String::new();
+            write_normalized(&settings, &mut normalized);
+            normalized
+        }
+        // Settings that are not JSON can only be compared as they are
+        Err(_) => entry.preset_data.clone(),
+    };
+
+    fnv1a(FNV_OFFSET, normalized.as_bytes())
+}
+
+pub fn image_hash(entry: &LoadedPresetEntry) -> u64 {
+    match &entry.broken {
+        // An image that could not be parsed is only known by its bytes
+        Some(BrokenPreset {
+            image_file: Some(image_file),
+            ..
+        }) => fnv1a(FNV_OFFSET, image_file),
+        _ => fnv1a(FNV_OFFSET, &write_lsi_image(&entry.image)),
+    }
+}
+
+/// A hash of the settings and the image of a preset, equal for presets with the same content
+/// regardless of their slot, file names or metadata
+pub fn content_hash(entry: &LoadedPresetEntry) -> u64 {
+    fnv1a(settings_hash(entry), &image_hash(entry).to_le_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{PointF, Side};
+
+    fn entry(settings: &str, image: Vec<Vec<PointF>>) -> LoadedPresetEntry {
+        LoadedPresetEntry::new(
+            0,
+            Side::Left,
+            "image_timbre0_0.lsi".to_string(),
+            "settings_timbre0_0.json".to_string(),
+            image,
+            settings.to_string(),
+            None,
+        )
+    }
+
+    fn line() -> Vec<Vec<PointF>> {
+        vec![vec![PointF::new(0.0, 0.5), PointF::new(1.0, 0.5)]]
+    }
+
+    #[test]
+    fn key_order_and_whitespace_do_not_change_the_hash() {
+        let compact = entry(r#"{"name":"Pad","filter":{"cutoff":0.5,"reso":0.1}}"#, line());
+        let formatted = r#"{
+            "filter": { "reso": 0.1, "cutoff": 0.5 },
+            "name": "Pad"
+        }
+        "#;
+        let formatted = entry(formatted, line());
+
+        assert_eq!(settings_hash(&compact), settings_hash(&formatted));
+        assert_eq!(content_hash(&compact), content_hash(&formatted));
+    }
+
+    #[test]
+    fn values_array_order_and_images_change_the_hash() {
+        let original = entry(r#"{"steps":[1,2],"name":"Pad"}"#, line());
+        let other_value = entry(r#"{"steps":[1,2],"name":"Pads"}"#, line());
+        let other_order = entry(r#"{"steps":[2,1],"name":"Pad"}"#, line());
+        let other_image = entry(r#"{"steps":[1,2],"name":"Pad"}"#, Vec::new());
+
+        assert_ne!(settings_hash(&original), settings_hash(&other_value));
+        assert_ne!(settings_hash(&original), settings_hash(&other_order));
+        assert_eq!(settings_hash(&original), settings_hash(&other_image));
+        assert_ne!(content_hash(&original), content_hash(&other_image));
+    }
+
+    #[test]
+    fn the_slot_file_names_and_metadata_do_not_change_the_hash() {
+        let original = entry("{}", line());
+        let mut moved = LoadedPresetEntry::new(
+            9,
+            Side::Right,
+            "image_timbre1_3.lsi".to_string(),
+            "settings_timbre1_3.json".to_string(),
+            line(),
+            "{}".to_string(),
+            None,
+        );
+        moved.metadata.tags = vec!["warm".to_string()];
+
+        assert_eq!(content_hash(&original), content_hash(&moved));
+    }
+}
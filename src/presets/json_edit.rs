@@ -0,0 +1,235 @@
+use serde_json::Value;
+use std::ops::Range;
+
+/// Walks JSON text without building a tree, to find where a value is written
+struct Scanner<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl Scanner<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\r' | b'\n')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.skip_whitespace();
+        if self.peek()? != byte {
+            return None;
+        }
+        self.pos += 1;
+        Some(())
+    }
+
+    /// Skips a string including its quotes
+    fn string(&mut self) -> Option<Range<usize>> {
+        let start = self.pos;
+        self.expect(b'"')?;
+        loop {
+            match self.peek()? {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Some(start..self.pos);
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    /// Skips a whole value and returns where it is
+    fn value(&mut self) -> Option<Range<usize>> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek()? {
+            b'{' => {
+                self.members(|_, scanner| scanner.value().map(|_| None))?;
+            }
+            b'[' => {
+                self.elements(|_, scanner| scanner.value().map(|_| None))?;
+            }
+            b'"' => {
+                self.string()?;
+            }
+            _ => {
+                while !matches!(
+                    self.peek(),
+                    None | Some(b',' | b'}' | b']' | b' ' | b'\t' | b'\r' | b'\n')
+                ) {
+                    self.pos += 1;
+                }
+            }
+        }
+
+        (self.pos > start).then_some(start..self.pos)
+    }
+
+    /// Goes through the members of an object, stopping early when `member` returns a result
+    fn members(
+        &mut self,
+        mut member: impl FnMut(&str, &mut Self) -> Option<Option<Range<usize>>>,
+    ) -> Option<Option<Range<usize>>> {
+        self.expect(b'{')?;
+        self.skip_whitespace();
+        if self.peek()? == b'}' {
+            self.pos += 1;
+            return Some(None);
+        }
+        loop {
+            self.skip_whitespace();
+            let key_span = self.string()?;
+            let key: String = serde_json::from_slice(&self.text[key_span]).ok()?;
+            self.expect(b':')?;
+            if let Some(found) = member(&key, self)? {
+                return Some(Some(found));
+            }
+            self.skip_whitespace();
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b'}' => {
+                    self.pos += 1;
+                    return Some(None);
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Goes through the elements of an array, stopping early when `element` returns a result
+    fn elements(
+        &mut self,
+        mut element: impl FnMut(usize, &mut Self) -> Option<Option<Range<usize>>>,
+    ) -> Option<Option<Range<usize>>> {
+        self.expect(b'[')?;
+        self.skip_whitespace();
+        if self.peek()? == b']' {
+            self.pos += 1;
+            return Some(None);
+        }
+        for i in 0.. {
+            if let Some(found) = element(i, self)? {
+                return Some(Some(found));
+            }
+            self.skip_whitespace();
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b']' => {
+                    self.pos += 1;
+                    return Some(None);
+                }
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    fn find(&mut self, path: &[&str]) -> Option<Range<usize>> {
+        let Some((first, rest)) = path.split_first() else {
+            return self.value();
+        };
+
+        self.skip_whitespace();
+        match self.peek()? {
+            b'{' => self.members(|key, scanner| {
+                if key == *first {
+                    Some(Some(scanner.find(rest)?))
+                } else {
+                    scanner.value().map(|_| None)
+                }
+            })?,
+            b'[' => {
+                let index = first.parse::<usize>().ok()?;
+                self.elements(|i, scanner| {
+                    if i == index {
+                        Some(Some(scanner.find(rest)?))
+                    } else {
+                        scanner.value().map(|_| None)
+                    }
+                })?
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Replaces the value at a dot separated path in JSON text, leaving the rest of the text exactly as
+/// it was so the key order, formatting and number notation of other values are kept
+pub fn replace_value(json: &str, path: &str, value: &Value) -> Result<String, String> {
+    let keys: Vec<&str> = path.split('.').collect();
+    let mut scanner = Scanner {
+        text: json.as_bytes(),
+        pos: 0,
+    };
+    let span = scanner
+        .find(&keys)
+        .ok_or_else(|| format!("Cannot find {path} in the settings"))?;
+
+    let literal = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    let mut result = String::with_capacity(json.len() + literal.len());
+    result.push_str(&json[..span.start]);
+    result.push_str(&literal);
+    result.push_str(&json[span.end..]);
+
+    // Make sure the change landed where it was meant to
+    let check: Value = serde_json::from_str(&result)
+        .map_err(|e| format!("Changing {path} would break the settings: {e}"))?;
+    let pointer: Vec<String> = keys
+        .iter()
+        .map(|k| k.replace('~', "~0").replace('/', "~1"))
+        .collect();
+    if check.pointer(&format!("/{}", pointer.join("/"))) != Some(value) {
+        return Err(format!("Cannot change {path} in the settings"));
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SETTINGS: &str = r#"{
+  "name": "Pad \"Warm\"",
+  "filter": { "cutoff": 0.50, "type": "lowpass" },
+  "envelopes": [ { "attack": 1e-2 }, { "attack": 0.2 } ],
+  "a.b": 1
+}"#;
+
+    #[test]
+    fn only_the_replaced_value_changes() {
+        let result = replace_value(SETTINGS, "filter.cutoff", &json!(0.75)).unwrap();
+        assert_eq!(result, SETTINGS.replace("0.50", "0.75"));
+    }
+
+    #[test]
+    fn values_in_arrays_are_found_by_index() {
+        let result = replace_value(SETTINGS, "envelopes.1.attack", &json!(0.5)).unwrap();
+        assert_eq!(result, SETTINGS.replace("0.2 }", "0.5 }"));
+    }
+
+    #[test]
+    fn strings_are_written_escaped() {
+        let result = replace_value(SETTINGS, "name", &json!("Pad \"Cold\"")).unwrap();
+        assert_eq!(result, SETTINGS.replace("Warm", "Cold"));
+    }
+
+    #[test]
+    fn missing_paths_are_an_error() {
+        assert!(replace_value(SETTINGS, "filter.drive", &json!(1)).is_err());
+        assert!(replace_value(SETTINGS, "envelopes.2.attack", &json!(1)).is_err());
+        assert!(replace_value(SETTINGS, "name.first", &json!(1)).is_err());
+    }
+
+    #[test]
+    fn keys_with_dots_cannot_be_addressed() {
+        // The path splits at every dot, so a key holding one is never matched
+        assert!(replace_value(SETTINGS, "a.b", &json!(2)).is_err());
+    }
+}
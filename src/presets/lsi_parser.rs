@@ -1,25 +1,98 @@
 use crate::types::{ImageData, PointF};
-use nom::combinator::all_consuming;
-use nom::multi::{many0, many_m_n};
-use nom::number::complete::{le_f32, le_i32};
-use nom::sequence::tuple;
-use nom::IResult;
-
-fn parse_point_sequence(input: &[u8]) -> IResult<&[u8], Vec<PointF>> {
-    let (inp, point_count) = le_i32(input)?;
-    let (inp, points) = many_m_n(
-        point_count as usize,
-        point_count as usize,
-        tuple((le_f32, le_f32)),
-    )(inp)?;
-    let points = points.into_iter().map(PointF::from).collect();
-
-    Ok((inp, points))
+use thiserror::Error;
+
+/// Size in bytes of a point count and of a coordinate
+const FIELD_SIZE: usize = 4;
+/// Size in bytes of a point, an `x` and a `y` coordinate
+const POINT_SIZE: usize = 2 * FIELD_SIZE;
+
+/// Why an image could not be parsed and where in its data
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("{message} at byte {offset}")]
+pub struct LsiError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl LsiError {
+    fn new(offset: usize, message: impl Into<String>) -> Self {
+        Self {
+            offset,
+            message: message.into(),
+        }
+    }
+}
+
+/// The 4 bytes at `offset`, which the caller has checked are there
+fn field(data: &[u8], offset: usize) -> [u8; FIELD_SIZE] {
+    data[offset..offset + FIELD_SIZE]
+        .try_into()
+        .expect("a field is 4 bytes")
+}
+
+fn parse_coordinate(data: &[u8], offset: usize, axis: &str) -> Result<f32, LsiError> {
+    let value = f32::from_le_bytes(field(data, offset));
+    if !value.is_finite() {
+        return Err(LsiError::new(offset, format!("The {axis} coordinate is {value}")));
+    }
+    if !(0.0..=1.0).contains(&value) {
+        return Err(LsiError::new(
+            offset,
+            format!("The {axis} coordinate {value} is outside of 0 to 1"),
+        ));
+    }
+
+    Ok(value)
+}
+
+/// Parses the line starting at `offset`, returns it with the offset of the next line
+fn parse_line(data: &[u8], offset: usize) -> Result<(Vec<PointF>, usize), LsiError> {
+    let left = data.len() - offset;
+    if left < FIELD_SIZE {
+        return Err(LsiError::new(
+            offset,
+            format!("A point count needs 4 bytes but only {left} are left"),
+        ));
+    }
+    let count = i32::from_le_bytes(field(data, offset));
+    let count = usize::try_from(count)
+        .map_err(|_| LsiError::new(offset, format!("The point count {count} is negative")))?;
+    // Checked before anything is allocated, so a corrupt count cannot ask for more memory than
+    // the data itself takes
+    let points_offset = offset + FIELD_SIZE;
+    let left = data.len() - points_offset;
+    if count > left / POINT_SIZE {
+        return Err(LsiError::new(
+            offset,
+            format!("{count} points do not fit in the {left} bytes left"),
+        ));
+    }
+
+    let points = (0..count)
+        .map(|i| {
+            let point_offset = points_offset + i * POINT_SIZE;
+            Ok(PointF::new(
+                parse_coordinate(data, point_offset, "x")?,
+                parse_coordinate(data, point_offset + FIELD_SIZE, "y")?,
+            ))
+        })
+        .collect::<Result<Vec<_>, LsiError>>()?;
+
+    Ok((points, points_offset + count * POINT_SIZE))
 }
 
-pub fn parse_lsi_image(data: &[u8]) -> anyhow::Result<ImageData> {
-    let (_, res) = all_consuming(many0(parse_point_sequence))(data)
-        .map_err(|e| anyhow::format_err!("Error occurred while parsing image: {e:?}"))?;
+/// Parses an image in the LSI format, a sequence of lines that each are a little endian `i32`
+/// point count followed by that many pairs of `f32` coordinates. Counts that are negative or
+/// larger than the data, and coordinates that are not numbers between 0 and 1 are rejected with
+/// the offset of the bad value.
+pub fn parse_lsi_image(data: &[u8]) -> Result<ImageData, LsiError> {
+    let mut image = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let (line, next) = parse_line(data, offset)?;
+        image.push(line);
+        offset = next;
+    }
 
-    Ok(res)
+    Ok(image)
 }
@@ -0,0 +1,18 @@
+use crate::types::ImageData;
+use itertools::Itertools;
+
+/// Encodes an image in the LSI format read by [`parse_lsi_image`](super::lsi_parser::parse_lsi_image):
+/// every line is a little endian `i32` point count followed by that many pairs of `f32` coordinates
+pub fn write_lsi_image(image: &ImageData) -> Vec<u8> {
+    image
+        .iter()
+        .flat_map(|line| {
+            let line_bytes = line
+                .iter()
+                .flat_map(|p| [p.x.to_le_bytes(), p.y.to_le_bytes()].concat())
+                .collect_vec();
+
+            [(line.len() as i32).to_le_bytes().to_vec(), line_bytes].concat()
+        })
+        .collect_vec()
+}
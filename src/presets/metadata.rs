@@ -0,0 +1,78 @@
+use crate::presets::error::BankError;
+use crate::presets::staging::write_synced;
+use crate::presets::{SYSTEM_METADATA_FILE, TIMBRE_METADATA_FILE};
+use crate::types::{LoadedPresetEntry, PresetMetadata, PresetType};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Name of the file next to a bank that holds the metadata of its presets
+pub fn metadata_file(preset_type: PresetType) -> &'static str {
+    match preset_type {
+        PresetType::Timbre => TIMBRE_METADATA_FILE,
+        PresetType::System => SYSTEM_METADATA_FILE,
+    }
+}
+
+/// Reads the metadata of a bank, keyed by preset file name. A bank without a metadata file has
+/// no metadata.
+pub fn load_metadata(
+    path: impl AsRef<Path>,
+    preset_type: PresetType,
+) -> Result<HashMap<String, PresetMetadata>, BankError> {
+    let file = path.as_ref().join(metadata_file(preset_type));
+    if !file.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let contents = fs::read_to_string(&file).map_err(|e| BankError::read(&file, e))?;
+    serde_json::from_str(&contents).map_err(|e| BankError::json(&file, e))
+}
+
+/// Copies the metadata file of the bank in `path` into `staging` when it cannot be read and the
+/// save wrote no metadata of its own, so a file that only needs fixing by hand is not lost
+pub fn keep_unreadable_metadata(
+    path: impl AsRef<Path>,
+    staging: impl AsRef<Path>,
+    preset_type: PresetType,
+) -> Result<(), BankError> {
+    let (file, staged) = (
+        path.as_ref().join(metadata_file(preset_type)),
+        staging.as_ref().join(metadata_file(preset_type)),
+    );
+    if staged.exists() || load_metadata(&path, preset_type).is_ok() {
+        return Ok(());
+    }
+
+    fs::copy(&file, staged).map_err(|e| BankError::io("copy", file, e))?;
+    Ok(())
+}
+
+/// Writes the metadata of `presets` keyed by their preset file names, which must already be the
+/// names they are saved under. Nothing is written when no preset has metadata.
+pub fn write_metadata(
+    path: impl AsRef<Path>,
+    presets: &HashMap<i32, LoadedPresetEntry>,
+    preset_type: PresetType,
+) -> Result<(), BankError> {
+    // Sorted so the file is stable between saves and readable in a diff
+    let metadata: BTreeMap<&str, PresetMetadata> = presets
+        .values()
+        .filter(|p| !p.metadata.is_empty())
+        .map(|p| {
+            let mut metadata = p.metadata.clone();
+            metadata.tags.retain(|t| !t.trim().is_empty());
+            (p.preset_filename.as_str(), metadata)
+        })
+        .collect();
+    if metadata.is_empty() {
+        return Ok(());
+    }
+
+    let file = path.as_ref().join(metadata_file(preset_type));
+    let json = serde_json::to_string_pretty(&metadata).map_err(|error| BankError::Serialize {
+        path: file.clone(),
+        error,
+    })?;
+    write_synced(file, json)
+}
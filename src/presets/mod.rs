@@ -1,17 +1,41 @@
-mod lsi_parser;
+pub mod backup;
+pub mod bank_diff;
+pub mod compare;
+pub mod duplicates;
+pub mod error;
+pub mod hash;
+mod json_edit;
+pub mod lsi_parser;
+pub mod lsi_writer;
+pub mod metadata;
+pub mod operations;
+pub mod parameters;
+pub mod search;
+pub mod sort;
+mod staging;
+pub mod validate;
 
 use crate::presets::lsi_parser::parse_lsi_image;
+use crate::presets::lsi_writer::write_lsi_image;
+use crate::presets::metadata::{keep_unreadable_metadata, load_metadata, write_metadata};
+use crate::presets::staging::{
+    abort_staging, begin_staging, commit_staging, has_interrupted_save, recover_interrupted_save,
+    write_synced,
+};
 use crate::types::PresetType::{System, Timbre};
 use crate::types::{
-    ImageData, LoadedPresetEntry, PointF, PresetData, PresetEntry, PresetType, Side,
+    BrokenPreset, ImageData, LoadedPresetEntry, PointF, PresetData, PresetEntry, PresetType, Side,
 };
-use anyhow::anyhow;
-use itertools::Itertools;
+use crate::presets::error::BankError;
+use serde_json::Value;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
-use std::fs;
 use std::path::Path;
+use std::time::SystemTime;
+use std::{fs, io};
 
-const SYNTH_IMAGE_COLUMNS: i32 = 6;
+/// Number of slots in each row of a bank, as the synth lays them out
+pub const SYNTH_IMAGE_COLUMNS: i32 = 6;
 const SYSTEM_PRESET_FILE_PREFIX: &str = "settings_system";
 const TIMBRE_PRESET_FILE_PREFIX: &str = "settings_timbre";
 const SYSTEM_IMAGE_FILE_PREFIX: &str = "image_system";
@@ -19,64 +43,198 @@ const TIMBRE_IMAGE_FILE_PREFIX: &str = "image_timbre";
 const SYSTEM_PRESET_LIST_FILE: &str = "system_preset_list.json";
 const TIMBRE_PRESET_LIST_FILE: &str = "preset_list.json";
 const SYSTEM_AUTOSAVE_FILE: &str = "settings_system_auto.json";
+const SYSTEM_METADATA_FILE: &str = "system_preset_metadata.json";
+const TIMBRE_METADATA_FILE: &str = "preset_metadata.json";
+
+/// Reads the entries of a preset list file in the order they are listed
+pub fn read_preset_list(path: impl AsRef<Path>) -> Result<Vec<PresetEntry>, BankError> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path).map_err(|e| match BankError::read(path, e) {
+        BankError::MissingFile { path } => BankError::MissingList { path },
+        error => error,
+    })?;
+
+    serde_json::from_str(&contents).map_err(|e| BankError::json(path, e))
+}
 
-pub fn load_preset_list(path: impl AsRef<Path>) -> anyhow::Result<HashMap<i32, PresetEntry>> {
-    let preset_list: Vec<PresetEntry> = serde_json::from_str(&fs::read_to_string(path)?)?;
-    let preset_map = preset_list.into_iter().map(|p| (p.index, p)).collect();
+/// Reads a preset list file, keyed by preset index. When entries share an index the first one
+/// listed has the slot, the others are returned apart in the order they are listed.
+pub fn load_preset_list(
+    path: impl AsRef<Path>,
+) -> Result<(HashMap<i32, PresetEntry>, Vec<PresetEntry>), BankError> {
+    let mut preset_map = HashMap::new();
+    let mut displaced = Vec::new();
+    for p in read_preset_list(path)? {
+        match preset_map.entry(p.index) {
+            Entry::Occupied(_) => displaced.push(p),
+            Entry::Vacant(slot) => {
+                slot.insert(p);
+            }
+        }
+    }
 
-    Ok(preset_map)
+    Ok((preset_map, displaced))
 }
 
-pub fn load_preset_image(path: impl AsRef<Path>) -> anyhow::Result<Vec<Vec<PointF>>> {
-    let data = fs::read(path)?;
-    parse_lsi_image(data.as_slice())
+/// Reads and parses an LSI image file
+pub fn load_preset_image(path: impl AsRef<Path>) -> Result<Vec<Vec<PointF>>, BankError> {
+    let path = path.as_ref();
+    let data = fs::read(path).map_err(|e| BankError::read(path, e))?;
+    parse_lsi_image(data.as_slice()).map_err(|error| BankError::Image {
+        path: path.to_path_buf(),
+        error,
+    })
 }
 
-pub fn load_presets(path: &str, preset_type: PresetType, side: Side) -> anyhow::Result<PresetData> {
+/// Loads the bank of the given type from the folder at `path` without changing anything there.
+/// `side` is recorded as the original side of every loaded preset. Presets whose files are missing
+/// or broken are loaded as broken entries and an unreadable metadata file is skipped rather than
+/// failing, a bank with an interrupted save fails until it is opened with [`open_presets`].
+pub fn load_presets(
+    path: &str,
+    preset_type: PresetType,
+    side: Side,
+) -> Result<PresetData, BankError> {
     use PresetType::*;
     let preset_list_file = match preset_type {
         Timbre => TIMBRE_PRESET_LIST_FILE,
         System => SYSTEM_PRESET_LIST_FILE,
     };
-    let preset_list = load_preset_list(&format!("{path}/{preset_list_file}"))?;
-    let loaded_preset_list: anyhow::Result<HashMap<i32, LoadedPresetEntry>> = preset_list
+    if has_interrupted_save(path, preset_type) {
+        return Err(BankError::InterruptedSave { path: path.into() });
+    }
+    let (preset_list, displaced) = load_preset_list(format!("{path}/{preset_list_file}"))?;
+    let (mut metadata, metadata_error) = match load_metadata(path, preset_type) {
+        Ok(metadata) => (metadata, None),
+        Err(e) => (HashMap::new(), Some(e.to_string())),
+    };
+    let loaded_preset_list: HashMap<i32, LoadedPresetEntry> = preset_list
         .into_iter()
         .map(|(index, p)| {
-            let image_data = load_preset_image(format!("{path}/{}", &p.image_filename))?;
-            Ok((
-                index,
-                LoadedPresetEntry::new(
-                    p.index,
-                    side,
-                    p.image_filename,
-                    p.preset_filename.clone(),
-                    image_data,
-                    fs::read_to_string(format!("{path}/{}", p.preset_filename))?,
-                ),
-            ))
+            let mut entry = load_entry(path, p, side);
+            entry.metadata = metadata.remove(&entry.preset_filename).unwrap_or_default();
+            (index, entry)
         })
         .collect();
     let preset_name = Path::new(path)
         .file_name()
-        .map(|n| n.to_str())
-        .flatten()
+        .and_then(|n| n.to_str())
         .unwrap_or("Untitled");
 
     Ok(PresetData {
         path: path.to_string(),
         preset_type,
-        presets: loaded_preset_list?,
+        presets: loaded_preset_list,
         name: preset_name.to_string(),
+        metadata_error,
+        displaced: displaced.into_iter().map(|p| p.preset_filename).collect(),
     })
 }
 
-fn preset_filename_location(index: i32) -> String {
+/// Opens the bank in the folder at `path` for editing, finishing any save that was interrupted
+/// there before loading it like [`load_presets`]
+pub fn open_presets(
+    path: &str,
+    preset_type: PresetType,
+    side: Side,
+) -> Result<PresetData, BankError> {
+    recover_interrupted_save(path, preset_type)?;
+    load_presets(path, preset_type, side)
+}
+
+/// Reads an image file, returning its raw bytes when there are any with the parsed image
+pub(crate) fn read_image(path: &Path) -> (Option<Vec<u8>>, Result<ImageData, BankError>) {
+    match fs::read(path) {
+        Ok(data) => {
+            let image = parse_lsi_image(&data).map_err(|error| BankError::Image {
+                path: path.to_path_buf(),
+                error,
+            });
+            (Some(data), image)
+        }
+        Err(e) => (None, Err(BankError::read(path, e))),
+    }
+}
+
+/// Reads a settings file, returning its raw bytes when there are any with the settings, which
+/// have to be JSON. The editor and the validator both read settings this way, so a preset the
+/// validator reports is the one the editor shows as broken.
+pub(crate) fn read_settings(path: &Path) -> (Option<Vec<u8>>, Result<String, BankError>) {
+    match fs::read(path) {
+        Ok(data) => {
+            let settings = String::from_utf8(data.clone())
+                .map_err(|e| {
+                    BankError::io("read", path, io::Error::new(io::ErrorKind::InvalidData, e))
+                })
+                .and_then(|settings| match serde_json::from_str::<Value>(&settings) {
+                    Ok(_) => Ok(settings),
+                    Err(e) => Err(BankError::json(path, e)),
+                });
+            (Some(data), settings)
+        }
+        Err(e) => (None, Err(BankError::read(path, e))),
+    }
+}
+
+/// Reads the files of a preset list entry. A preset whose files are missing or broken is loaded
+/// with an empty image and the reason in [`LoadedPresetEntry::broken`].
+fn load_entry(path: &str, p: PresetEntry, side: Side) -> LoadedPresetEntry {
+    let image_path = Path::new(path).join(&p.image_filename);
+    let preset_path = Path::new(path).join(&p.preset_filename);
+    let modified = fs::metadata(&preset_path).and_then(|m| m.modified()).ok();
+
+    let (image_file, image) = read_image(&image_path);
+    let (settings_file, settings) = read_settings(&preset_path);
+    let reasons: Vec<String> = [image.as_ref().err(), settings.as_ref().err()]
+        .into_iter()
+        .flatten()
+        .map(BankError::to_string)
+        .collect();
+
+    let mut entry = LoadedPresetEntry::new(
+        p.index,
+        side,
+        p.image_filename,
+        p.preset_filename,
+        image.unwrap_or_default(),
+        settings.unwrap_or_else(|_| {
+            settings_file
+                .as_deref()
+                .map(|data| String::from_utf8_lossy(data).into_owned())
+                .unwrap_or_default()
+        }),
+        modified,
+    );
+    if !reasons.is_empty() {
+        entry.broken = Some(BrokenPreset {
+            reason: reasons.join(", "),
+            image_file,
+            settings_file,
+        });
+    }
+
+    entry
+}
+
+/// The `row_column` part of the file names of the preset in slot `index`
+pub fn preset_filename_location(index: i32) -> String {
     let row = index / SYNTH_IMAGE_COLUMNS;
     let column = index % SYNTH_IMAGE_COLUMNS;
 
     format!("{row}_{column}")
 }
 
+/// The slot index for a `row_column` location, the inverse of [`preset_filename_location`]
+pub fn preset_index_from_location(location: &str) -> Option<i32> {
+    let (row, column) = location.split_once('_')?;
+    let (row, column) = (row.parse::<i32>().ok()?, column.parse::<i32>().ok()?);
+    if row < 0 || !(0..SYNTH_IMAGE_COLUMNS).contains(&column) {
+        return None;
+    }
+
+    Some(row * SYNTH_IMAGE_COLUMNS + column)
+}
+
 fn preset_image_filename(index: i32, preset_type: PresetType) -> String {
     use PresetType::*;
     let prefix = match preset_type {
@@ -99,30 +257,56 @@ fn preset_filename(index: i32, preset_type: PresetType) -> String {
     format!("{prefix}{suffix}.json")
 }
 
-fn save_image(path: impl AsRef<Path>, image: &ImageData) -> anyhow::Result<()> {
-    let image_data = image
-        .into_iter()
-        .flat_map(|line| {
-            let line_bytes = line
-                .iter()
-                .flat_map(|p| [p.x.to_le_bytes(), p.y.to_le_bytes()].concat())
-                .collect_vec();
+fn save_image(path: impl AsRef<Path>, image: &ImageData) -> Result<(), BankError> {
+    write_synced(path, write_lsi_image(image))
+}
 
-            [(line.len() as i32).to_le_bytes().to_vec(), line_bytes].concat()
-        })
-        .collect_vec();
+/// Writes a settings file with the modification time the preset had when it was loaded, so a save
+/// that rewrites every file of the bank keeps the dates of presets it did not change
+fn save_settings(
+    path: &Path,
+    contents: impl AsRef<[u8]>,
+    modified: Option<SystemTime>,
+) -> Result<(), BankError> {
+    write_synced(path, contents)?;
+    if let Some(modified) = modified {
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_modified(modified))
+            .map_err(|e| BankError::io("write", path, e))?;
+    }
 
-    fs::write(path, image_data)?;
     Ok(())
 }
 
+/// Writes `presets` as the bank of the given type into the folder at `path`, naming the files
+/// after the slot each preset is in. The previous bank files are only replaced once the whole new
+/// bank has been written, any other files in the folder are left alone.
 pub fn save_presets(
     path: &str,
     presets: &HashMap<i32, LoadedPresetEntry>,
     preset_type: PresetType,
-) -> anyhow::Result<()> {
-    remove_preset_files_from_dir(path, preset_type)?;
+) -> Result<(), BankError> {
+    recover_interrupted_save(path, preset_type)?;
 
+    let staging = begin_staging(path, preset_type)?;
+    let written = write_presets(&staging, presets, preset_type)
+        .and_then(|_| keep_unreadable_metadata(path, &staging, preset_type));
+    if let Err(e) = written {
+        abort_staging(path, preset_type);
+        let e = e.in_folder(&staging, Path::new(path));
+        return Err(BankError::SaveAborted(Box::new(e)));
+    }
+
+    commit_staging(path, preset_type)
+}
+
+fn write_presets(
+    path: &Path,
+    presets: &HashMap<i32, LoadedPresetEntry>,
+    preset_type: PresetType,
+) -> Result<(), BankError> {
     let presets: HashMap<i32, LoadedPresetEntry> = presets
         .iter()
         .map(|(i, p)| {
@@ -137,19 +321,32 @@ pub fn save_presets(
         })
         .collect();
 
-    for (_, entry) in &presets {
-        save_image(format!("{path}/{}", &entry.image_filename), &entry.image)?;
-        fs::write(
-            format!("{path}/{}", &entry.preset_filename),
-            &entry.preset_data,
-        )?;
+    for entry in presets.values() {
+        match &entry.broken {
+            // Written back as they were read, files that could not be read stay missing
+            Some(broken) => {
+                if let Some(image_file) = &broken.image_file {
+                    write_synced(path.join(&entry.image_filename), image_file)?;
+                }
+                if let Some(settings_file) = &broken.settings_file {
+                    let settings_path = path.join(&entry.preset_filename);
+                    save_settings(&settings_path, settings_file, entry.modified)?;
+                }
+            }
+            None => {
+                save_image(path.join(&entry.image_filename), &entry.image)?;
+                let settings_path = path.join(&entry.preset_filename);
+                save_settings(&settings_path, &entry.preset_data, entry.modified)?;
+            }
+        }
     }
+    write_metadata(path, &presets, preset_type)?;
 
     let preset_list_filename = match preset_type {
         Timbre => TIMBRE_PRESET_LIST_FILE,
         System => SYSTEM_PRESET_LIST_FILE,
     };
-    let preset_list: Vec<PresetEntry> = presets
+    let mut preset_list: Vec<PresetEntry> = presets
         .into_iter()
         .map(|(i, p)| PresetEntry {
             index: i,
@@ -157,42 +354,12 @@ pub fn save_presets(
             image_filename: p.image_filename,
         })
         .collect();
-    let preset_json = serde_json::to_string(&preset_list)?;
-    fs::write(format!("{path}/{preset_list_filename}"), preset_json)?;
-
-    Ok(())
-}
-
-fn remove_preset_files_from_dir(path: &str, preset_type: PresetType) -> anyhow::Result<()> {
-    let (image_prefix, preset_prefix, preset_list) = match preset_type {
-        Timbre => (
-            TIMBRE_IMAGE_FILE_PREFIX,
-            TIMBRE_PRESET_FILE_PREFIX,
-            TIMBRE_PRESET_LIST_FILE,
-        ),
-        System => (
-            SYSTEM_IMAGE_FILE_PREFIX,
-            SYSTEM_PRESET_FILE_PREFIX,
-            SYSTEM_PRESET_LIST_FILE,
-        ),
-    };
-
-    for entry in fs::read_dir(path)? {
-        let entry = entry?;
-        let file_name = match entry.file_name().into_string() {
-            Ok(file_name) => file_name,
-            // Ignore files with invalid unicode filenames since we will never write to them
-            Err(_) => continue
-        };
-
-        if (file_name.starts_with(image_prefix)
-            || file_name.starts_with(preset_prefix)
-            || file_name == preset_list)
-            && file_name != SYSTEM_AUTOSAVE_FILE
-        {
-            fs::remove_file(entry.path())?;
-        }
-    }
-
-    Ok(())
+    // Sorted so saving an unchanged bank writes the same file again
+    preset_list.sort_by_key(|p| p.index);
+    let preset_list_path = path.join(preset_list_filename);
+    let preset_json = serde_json::to_string(&preset_list).map_err(|error| BankError::Serialize {
+        path: preset_list_path.clone(),
+        error,
+    })?;
+    write_synced(preset_list_path, preset_json)
 }
@@ -0,0 +1,544 @@
+use crate::presets::parameters::set_parameter;
+use crate::presets::sort::{sorted_presets, SortKey};
+use crate::presets::SYNTH_IMAGE_COLUMNS;
+use crate::types::{LoadedPresetEntry, PresetData, Side};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::SystemTime;
+
+/// What a block move or copy does with presets already in its target slots
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum OccupiedSlots {
+    /// Move the presets in the way into the slots the block left, copies replace them instead
+    #[default]
+    Swap,
+    /// Push the presets in the way further down the bank, as far as needed to make room
+    PushDown,
+    /// Do nothing and report an error
+    Refuse,
+}
+
+impl OccupiedSlots {
+    pub const ALL: [OccupiedSlots; 3] = [
+        OccupiedSlots::Swap,
+        OccupiedSlots::PushDown,
+        OccupiedSlots::Refuse,
+    ];
+}
+
+impl fmt::Display for OccupiedSlots {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OccupiedSlots::Swap => "Swap",
+            OccupiedSlots::PushDown => "Push down",
+            OccupiedSlots::Refuse => "Refuse",
+        })
+    }
+}
+
+fn presets_mut(
+    preset_lists: &mut HashMap<Side, PresetData>,
+    side: Side,
+) -> &mut HashMap<i32, LoadedPresetEntry> {
+    &mut preset_lists.get_mut(&side).unwrap().presets
+}
+
+/// Moves a preset to another slot, on the same side or the other one. If the target slot holds a
+/// preset the two presets are swapped.
+pub fn move_preset(
+    preset_lists: &mut HashMap<Side, PresetData>,
+    from: i32,
+    from_side: Side,
+    to: i32,
+    to_side: Side,
+) -> Result<(), String> {
+    if !preset_lists.contains_key(&from_side) || !preset_lists.contains_key(&to_side) {
+        return Err("Either side is not loaded, cannot proceed with move".to_string());
+    }
+    if !preset_lists[&from_side].presets.contains_key(&from) {
+        return Err("Selected item does not exist, cannot move".to_string());
+    }
+
+    let from_preset = presets_mut(preset_lists, from_side).remove(&from).unwrap();
+    let to_preset = presets_mut(preset_lists, to_side).remove(&to);
+
+    presets_mut(preset_lists, to_side).insert(to, from_preset);
+
+    // Swap the items if there is a preset in both slots
+    if let Some(e) = to_preset {
+        presets_mut(preset_lists, from_side).insert(from, e);
+    }
+
+    Ok(())
+}
+
+/// Copies a preset into another slot, leaving the source intact. Returns the preset that was
+/// overwritten in the target slot, if any.
+pub fn copy_preset(
+    preset_lists: &mut HashMap<Side, PresetData>,
+    from: i32,
+    from_side: Side,
+    to: i32,
+    to_side: Side,
+) -> Result<Option<LoadedPresetEntry>, String> {
+    if !preset_lists.contains_key(&from_side) || !preset_lists.contains_key(&to_side) {
+        return Err("Either side is not loaded, cannot proceed with copy".to_string());
+    }
+    if from == to && from_side == to_side {
+        return Err("Cannot copy a preset onto itself".to_string());
+    }
+    let Some(from_preset) = preset_lists[&from_side].presets.get(&from).cloned() else {
+        return Err("Selected item does not exist, cannot copy".to_string());
+    };
+
+    Ok(presets_mut(preset_lists, to_side).insert(to, from_preset))
+}
+
+/// Removes the presets in the given slots of a side, empty slots are ignored
+pub fn delete_presets(
+    preset_lists: &mut HashMap<Side, PresetData>,
+    side: Side,
+    indices: &[i32],
+) -> Result<Vec<LoadedPresetEntry>, String> {
+    if !preset_lists.contains_key(&side) {
+        return Err("Side is not loaded, cannot delete presets".to_string());
+    }
+
+    let presets = presets_mut(preset_lists, side);
+    Ok(indices.iter().filter_map(|i| presets.remove(i)).collect())
+}
+
+/// A block of presets to move or copy with [`move_block`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockMove {
+    pub from_side: Side,
+    /// Slots of the presets in the block
+    pub sources: Vec<i32>,
+    pub to_side: Side,
+    /// How many rows down the block moves, negative to move it up
+    pub row_offset: i32,
+    /// How many columns right the block moves, negative to move it left
+    pub column_offset: i32,
+    pub occupied: OccupiedSlots,
+    /// Copy the presets instead of moving them
+    pub copy: bool,
+}
+
+/// Moves or copies a block of presets, keeping their layout in the grid. Every preset in the
+/// block ends up `row_offset` rows and `column_offset` columns away, on `to_side`. Returns the
+/// slots the block was placed in.
+pub fn move_block(
+    preset_lists: &mut HashMap<Side, PresetData>,
+    block_move: &BlockMove,
+) -> Result<Vec<i32>, String> {
+    let BlockMove {
+        from_side,
+        ref sources,
+        to_side,
+        row_offset,
+        column_offset,
+        occupied,
+        copy,
+    } = *block_move;
+    if !preset_lists.contains_key(&from_side) || !preset_lists.contains_key(&to_side) {
+        return Err("Either side is not loaded, cannot proceed with move".to_string());
+    }
+    let mut sources = sources.to_vec();
+    sources.sort();
+    sources.dedup();
+    if sources.is_empty() {
+        return Err("No presets selected".to_string());
+    }
+    if let Some(missing) = sources
+        .iter()
+        .find(|i| !preset_lists[&from_side].presets.contains_key(i))
+    {
+        return Err(format!("Slot {missing} is empty, cannot move it"));
+    }
+
+    let mut targets = Vec::with_capacity(sources.len());
+    for source in &sources {
+        let row = source / SYNTH_IMAGE_COLUMNS + row_offset;
+        let column = source % SYNTH_IMAGE_COLUMNS + column_offset;
+        if row < 0 || !(0..SYNTH_IMAGE_COLUMNS).contains(&column) {
+            return Err("The selection does not fit in the grid at that position".to_string());
+        }
+        targets.push(row * SYNTH_IMAGE_COLUMNS + column);
+    }
+    if from_side == to_side && row_offset == 0 && column_offset == 0 {
+        return Ok(targets);
+    }
+
+    let moving_within_side = !copy && from_side == to_side;
+    let in_the_way: Vec<i32> = targets
+        .iter()
+        .copied()
+        .filter(|t| preset_lists[&to_side].presets.contains_key(t))
+        .filter(|t| !(moving_within_side && sources.contains(t)))
+        .collect();
+    if occupied == OccupiedSlots::Refuse && !in_the_way.is_empty() {
+        return Err(match in_the_way.len() {
+            1 => "A target slot already holds a preset".to_string(),
+            n => format!("{n} of the target slots already hold a preset"),
+        });
+    }
+
+    let block: Vec<LoadedPresetEntry> = if copy {
+        sources
+            .iter()
+            .map(|i| preset_lists[&from_side].presets[i].clone())
+            .collect()
+    } else {
+        let presets = presets_mut(preset_lists, from_side);
+        sources.iter().map(|i| presets.remove(i).unwrap()).collect()
+    };
+
+    match occupied {
+        OccupiedSlots::Swap if !copy => {
+            // Each preset in the way goes to where the preset taking its slot came from. If that
+            // slot is taken by the block as well, follow the chain to a slot the block left.
+            let source_of: HashMap<i32, i32> =
+                targets.iter().copied().zip(sources.iter().copied()).collect();
+            let taken: HashSet<i32> = if from_side == to_side {
+                targets.iter().copied().collect()
+            } else {
+                HashSet::new()
+            };
+            let displaced: Vec<(i32, LoadedPresetEntry)> = in_the_way
+                .iter()
+                .map(|t| (*t, presets_mut(preset_lists, to_side).remove(t).unwrap()))
+                .collect();
+            for (target, entry) in displaced {
+                let mut slot = source_of[&target];
+                while taken.contains(&slot) {
+                    slot = source_of[&slot];
+                }
+                presets_mut(preset_lists, from_side).insert(slot, entry);
+            }
+        }
+        OccupiedSlots::PushDown => {
+            let presets = presets_mut(preset_lists, to_side);
+            let target_set: HashSet<i32> = targets.iter().copied().collect();
+            let mut others: Vec<(i32, LoadedPresetEntry)> = presets.drain().collect();
+            others.sort_by_key(|(i, _)| *i);
+
+            let mut next_free = 0;
+            for (index, entry) in others {
+                let mut slot = index.max(next_free);
+                while target_set.contains(&slot) {
+                    slot += 1;
+                }
+                presets.insert(slot, entry);
+                next_free = slot + 1;
+            }
+        }
+        // Copies replace presets in the way, refusing was handled above
+        OccupiedSlots::Swap | OccupiedSlots::Refuse => (),
+    }
+
+    let presets = presets_mut(preset_lists, to_side);
+    for (target, entry) in targets.iter().zip(block) {
+        presets.insert(*target, entry);
+    }
+
+    Ok(targets)
+}
+
+/// Opens an empty slot at `index` by moving that preset and every preset after it one slot down
+pub fn insert_slot(
+    preset_lists: &mut HashMap<Side, PresetData>,
+    side: Side,
+    index: i32,
+) -> Result<(), String> {
+    if !preset_lists.contains_key(&side) {
+        return Err("Side is not loaded, cannot insert a slot".to_string());
+    }
+    if index < 0 {
+        return Err(format!("Slot {index} does not exist"));
+    }
+
+    let presets = presets_mut(preset_lists, side);
+    *presets = presets
+        .drain()
+        .map(|(i, entry)| if i >= index { (i + 1, entry) } else { (i, entry) })
+        .collect();
+
+    Ok(())
+}
+
+/// Removes the empty slot at `index` and moves every preset after it one slot up to close the gap
+pub fn remove_slot(
+    preset_lists: &mut HashMap<Side, PresetData>,
+    side: Side,
+    index: i32,
+) -> Result<(), String> {
+    if !preset_lists.contains_key(&side) {
+        return Err("Side is not loaded, cannot remove a slot".to_string());
+    }
+    if index < 0 {
+        return Err(format!("Slot {index} does not exist"));
+    }
+    if preset_lists[&side].presets.contains_key(&index) {
+        return Err("The slot holds a preset, delete it before removing the slot".to_string());
+    }
+
+    let presets = presets_mut(preset_lists, side);
+    *presets = presets
+        .drain()
+        .map(|(i, entry)| if i > index { (i - 1, entry) } else { (i, entry) })
+        .collect();
+
+    Ok(())
+}
+
+/// Closes every gap in a bank, keeping the order of its presets. Returns how many presets changed
+/// slots.
+pub fn compact_bank(
+    preset_lists: &mut HashMap<Side, PresetData>,
+    side: Side,
+) -> Result<usize, String> {
+    if !preset_lists.contains_key(&side) {
+        return Err("Side is not loaded, cannot compact it".to_string());
+    }
+
+    let presets = presets_mut(preset_lists, side);
+    let mut entries: Vec<(i32, LoadedPresetEntry)> = presets.drain().collect();
+    entries.sort_by_key(|(i, _)| *i);
+    let moved = entries
+        .iter()
+        .enumerate()
+        .filter(|(slot, (i, _))| *slot as i32 != *i)
+        .count();
+    presets.extend(
+        entries
+            .into_iter()
+            .enumerate()
+            .map(|(slot, (_, entry))| (slot as i32, entry)),
+    );
+
+    Ok(moved)
+}
+
+/// Sorts the presets in `slots` among those same slots, or the whole bank when `slots` is empty
+pub fn sort_presets(
+    preset_lists: &mut HashMap<Side, PresetData>,
+    side: Side,
+    slots: &[i32],
+    key: &SortKey,
+    descending: bool,
+) -> Result<(), String> {
+    let Some(preset_data) = preset_lists.get_mut(&side) else {
+        return Err("Side is not loaded, cannot sort it".to_string());
+    };
+
+    let slots: Vec<i32> = if slots.is_empty() {
+        preset_data.presets.keys().copied().collect()
+    } else {
+        slots.to_vec()
+    };
+    preset_data.presets = sorted_presets(&preset_data.presets, &slots, key, descending);
+
+    Ok(())
+}
+
+/// Changes a value in the settings of a preset, see [`set_parameter`]
+pub fn set_preset_parameter(
+    preset_lists: &mut HashMap<Side, PresetData>,
+    side: Side,
+    index: i32,
+    path: &str,
+    value: &serde_json::Value,
+) -> Result<(), String> {
+    let Some(entry) = preset_lists
+        .get_mut(&side)
+        .and_then(|p| p.presets.get_mut(&index))
+    else {
+        return Err("The preset no longer exists".to_string());
+    };
+    if entry.broken.is_some() {
+        return Err("The settings of a broken preset cannot be edited".to_string());
+    }
+
+    entry.preset_data = set_parameter(&entry.preset_data, path, value)?;
+    entry.modified = Some(SystemTime::now());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PresetType;
+    use Side::{Left, Right};
+
+    /// A bank on `side` with a preset in each of `slots`
+    fn bank(side: Side, slots: &[i32]) -> PresetData {
+        let presets = slots
+            .iter()
+            .map(|&i| {
+                let entry = LoadedPresetEntry::new(
+                    i,
+                    side,
+                    String::new(),
+                    String::new(),
+                    Vec::new(),
+                    "{}".to_string(),
+                    None,
+                );
+                (i, entry)
+            })
+            .collect();
+
+        PresetData {
+            path: String::new(),
+            preset_type: PresetType::Timbre,
+            presets,
+            name: String::new(),
+            metadata_error: None,
+            displaced: Vec::new(),
+        }
+    }
+
+    fn preset_lists(left: &[i32], right: &[i32]) -> HashMap<Side, PresetData> {
+        HashMap::from([(Left, bank(Left, left)), (Right, bank(Right, right))])
+    }
+
+    /// Every slot of a side with where the preset in it was loaded from
+    fn layout(preset_lists: &HashMap<Side, PresetData>, side: Side) -> Vec<(i32, Side, i32)> {
+        let mut layout: Vec<_> = preset_lists[&side]
+            .presets
+            .iter()
+            .map(|(i, p)| (*i, p.original_side, p.original_index))
+            .collect();
+        layout.sort_by_key(|(i, _, _)| *i);
+        layout
+    }
+
+    fn block(sources: &[i32], row_offset: i32, column_offset: i32) -> BlockMove {
+        BlockMove {
+            from_side: Left,
+            sources: sources.to_vec(),
+            to_side: Left,
+            row_offset,
+            column_offset,
+            occupied: OccupiedSlots::Swap,
+            copy: false,
+        }
+    }
+
+    #[test]
+    fn a_moved_block_keeps_its_layout() {
+        let mut lists = preset_lists(&[0, 1, 6], &[]);
+        let targets = move_block(&mut lists, &block(&[0, 1, 6], 1, 1)).unwrap();
+
+        assert_eq!(targets, [7, 8, 13]);
+        assert_eq!(layout(&lists, Left), [(7, Left, 0), (8, Left, 1), (13, Left, 6)]);
+    }
+
+    #[test]
+    fn a_block_that_leaves_the_grid_is_refused() {
+        let mut lists = preset_lists(&[5], &[]);
+        assert!(move_block(&mut lists, &block(&[5], 0, 1)).is_err());
+        assert!(move_block(&mut lists, &block(&[5], -1, 0)).is_err());
+        assert_eq!(layout(&lists, Left), [(5, Left, 5)]);
+    }
+
+    #[test]
+    fn swap_moves_presets_in_the_way_into_the_slots_the_block_left() {
+        let mut lists = preset_lists(&[0, 1, 2], &[]);
+        move_block(&mut lists, &block(&[0, 1], 0, 1)).unwrap();
+
+        // Slot 1 is taken by the block itself, so the preset from slot 2 follows on to slot 0
+        assert_eq!(layout(&lists, Left), [(0, Left, 2), (1, Left, 0), (2, Left, 1)]);
+    }
+
+    #[test]
+    fn swap_between_sides_exchanges_the_presets() {
+        let mut lists = preset_lists(&[0, 1], &[0]);
+        let block_move = BlockMove {
+            to_side: Right,
+            ..block(&[0, 1], 0, 0)
+        };
+        move_block(&mut lists, &block_move).unwrap();
+
+        assert_eq!(layout(&lists, Left), [(0, Right, 0)]);
+        assert_eq!(layout(&lists, Right), [(0, Left, 0), (1, Left, 1)]);
+    }
+
+    #[test]
+    fn push_down_makes_room_below_the_block() {
+        let mut lists = preset_lists(&[0], &[0, 1, 3]);
+        let block_move = BlockMove {
+            to_side: Right,
+            occupied: OccupiedSlots::PushDown,
+            ..block(&[0], 0, 0)
+        };
+        move_block(&mut lists, &block_move).unwrap();
+
+        assert!(lists[&Left].presets.is_empty());
+        assert_eq!(
+            layout(&lists, Right),
+            [(0, Left, 0), (1, Right, 0), (2, Right, 1), (3, Right, 3)]
+        );
+    }
+
+    #[test]
+    fn refuse_leaves_both_sides_alone_when_a_target_is_taken() {
+        let mut lists = preset_lists(&[0, 1], &[]);
+        let block_move = BlockMove {
+            occupied: OccupiedSlots::Refuse,
+            ..block(&[0], 0, 1)
+        };
+
+        assert!(move_block(&mut lists, &block_move).is_err());
+        assert_eq!(layout(&lists, Left), [(0, Left, 0), (1, Left, 1)]);
+    }
+
+    #[test]
+    fn copies_replace_presets_in_the_way() {
+        let mut lists = preset_lists(&[0], &[0]);
+        let block_move = BlockMove {
+            to_side: Right,
+            copy: true,
+            ..block(&[0], 0, 0)
+        };
+        move_block(&mut lists, &block_move).unwrap();
+
+        assert_eq!(layout(&lists, Left), [(0, Left, 0)]);
+        assert_eq!(layout(&lists, Right), [(0, Left, 0)]);
+    }
+
+    #[test]
+    fn inserting_a_slot_moves_the_presets_from_it_down() {
+        let mut lists = preset_lists(&[0, 2, 3], &[]);
+        insert_slot(&mut lists, Left, 2).unwrap();
+
+        assert_eq!(layout(&lists, Left), [(0, Left, 0), (3, Left, 2), (4, Left, 3)]);
+    }
+
+    #[test]
+    fn removing_a_slot_moves_the_presets_after_it_up() {
+        let mut lists = preset_lists(&[0, 2, 3], &[]);
+        assert!(remove_slot(&mut lists, Left, 2).is_err());
+        remove_slot(&mut lists, Left, 1).unwrap();
+
+        assert_eq!(layout(&lists, Left), [(0, Left, 0), (1, Left, 2), (2, Left, 3)]);
+    }
+
+    #[test]
+    fn slots_before_the_first_cannot_be_inserted_or_removed() {
+        let mut lists = preset_lists(&[0, 2], &[]);
+        assert!(insert_slot(&mut lists, Left, -1).is_err());
+        assert!(remove_slot(&mut lists, Left, -1).is_err());
+
+        assert_eq!(layout(&lists, Left), [(0, Left, 0), (2, Left, 2)]);
+    }
+
+    #[test]
+    fn compacting_closes_the_gaps_in_order() {
+        let mut lists = preset_lists(&[0, 4, 5], &[]);
+        assert_eq!(compact_bank(&mut lists, Left), Ok(2));
+        assert_eq!(layout(&lists, Left), [(0, Left, 0), (1, Left, 4), (2, Left, 5)]);
+
+        assert_eq!(compact_bank(&mut lists, Left), Ok(0));
+    }
+}
@@ -0,0 +1,395 @@
+use crate::presets::json_edit::replace_value;
+use serde_json::{Number, Value};
+
+/// Arrays of plain values longer than this are shown as one summarised parameter
+const MAX_EXPANDED_ARRAY: usize = 8;
+
+/// Section titles with the words that put a top level settings key into them, checked in order.
+/// A key is split into words and only whole words match, a plural `s` is ignored. Keys that match
+/// none of them are listed first, under "General".
+const SECTIONS: [(&str, &[&str]); 7] = [
+    (
+        "Oscillators",
+        &["osc", "oscillator", "wave", "waveform", "voice", "unison", "pitch", "tune", "detune"],
+    ),
+    ("Envelopes", &["env", "envelope", "adsr", "attack", "decay", "sustain", "release"]),
+    ("Filters", &["filter", "cutoff", "reso", "resonance", "vcf"]),
+    ("LFOs", &["lfo"]),
+    ("Modulation", &["mod", "modulation", "matrix"]),
+    (
+        "Effects",
+        &["fx", "effect", "reverb", "delay", "chorus", "distortion", "drive", "phaser", "eq"],
+    ),
+    ("Mixer", &["mix", "mixer", "volume", "gain", "pan", "output", "level", "amp"]),
+];
+
+/// Settings with a known range of values, matched against the last key of a parameter path, and
+/// whether they only take whole numbers. The range is only used when the current value is inside
+/// it.
+const KNOWN_RANGES: [(&[&str], f64, f64, bool); 4] = [
+    (
+        &[
+            "cutoff", "resonance", "reso", "mix", "level", "volume", "sustain", "depth", "amount",
+            "feedback", "drive",
+        ],
+        0.0,
+        1.0,
+        false,
+    ),
+    (&["pan", "balance"], -1.0, 1.0, false),
+    (&["transpose", "semitones"], -24.0, 24.0, true),
+    (&["octave"], -4.0, 4.0, true),
+];
+
+/// Settings that take one of a few names, used when the current value is one of them
+const KNOWN_CHOICES: [(&[&str], &[&str]); 3] = [
+    (
+        &["waveform", "wave", "shape"],
+        &["sine", "triangle", "saw", "square", "pulse", "noise"],
+    ),
+    (
+        &["filter_type", "filtertype", "type", "mode"],
+        &["lowpass", "highpass", "bandpass", "notch"],
+    ),
+    (&["polarity"], &["unipolar", "bipolar"]),
+];
+
+/// How a parameter can be edited
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterControl {
+    Toggle,
+    /// A number within a range, changed in steps
+    Slider { min: f64, max: f64, step: f64 },
+    /// A number without a known range
+    Number { integer: bool },
+    Choice(Vec<String>),
+    Text,
+    /// Lists, nulls and empty containers are only shown
+    ReadOnly,
+}
+
+/// Picks the control for a parameter from its name and current value
+pub fn parameter_control(path: &str, value: &Value) -> ParameterControl {
+    let key = path.rsplit('.').next().unwrap_or(path).to_lowercase();
+
+    match value {
+        Value::Bool(_) => ParameterControl::Toggle,
+        Value::Number(number) => {
+            let integer = number.is_i64() || number.is_u64();
+            let current = number.as_f64().unwrap_or(f64::NAN);
+            // A value outside the usual range for its name is on a scale of its own, like a
+            // cutoff in Hz, and a slider would clamp it
+            let known = KNOWN_RANGES.iter().find(|(keys, min, max, _)| {
+                keys.contains(&key.as_str()) && (*min..=*max).contains(&current)
+            });
+            match known {
+                // The step comes from the range, a level of 1 can still be set to 0.5
+                Some((_, min, max, whole)) => ParameterControl::Slider {
+                    min: *min,
+                    max: *max,
+                    step: if *whole { 1.0 } else { (max - min) / 1000.0 },
+                },
+                None => ParameterControl::Number { integer },
+            }
+        }
+        Value::String(current) => {
+            let choices = KNOWN_CHOICES.iter().find(|(keys, choices)| {
+                keys.contains(&key.as_str()) && choices.contains(&current.as_str())
+            });
+            match choices {
+                Some((_, choices)) => {
+                    ParameterControl::Choice(choices.iter().map(|c| c.to_string()).collect())
+                }
+                None => ParameterControl::Text,
+            }
+        }
+        _ => ParameterControl::ReadOnly,
+    }
+}
+
+/// Checks a new value against the control of the parameter it is meant for
+pub fn validate_parameter(control: &ParameterControl, value: &Value) -> Result<(), String> {
+    match (control, value) {
+        (ParameterControl::Toggle, Value::Bool(_)) | (ParameterControl::Text, Value::String(_)) => {
+            Ok(())
+        }
+        (ParameterControl::Slider { min, max, .. }, Value::Number(number)) => {
+            let number = number.as_f64().unwrap_or(f64::NAN);
+            if (*min..=*max).contains(&number) {
+                Ok(())
+            } else {
+                Err(format!("{number} is outside of the range {min} to {max}"))
+            }
+        }
+        (ParameterControl::Number { integer }, Value::Number(number)) => {
+            if *integer && !(number.is_i64() || number.is_u64()) {
+                Err(format!("{number} is not a whole number"))
+            } else {
+                Ok(())
+            }
+        }
+        (ParameterControl::Choice(choices), Value::String(choice)) => {
+            if choices.contains(choice) {
+                Ok(())
+            } else {
+                Err(format!("{choice} is not one of {}", choices.join(", ")))
+            }
+        }
+        (ParameterControl::ReadOnly, _) => Err("This parameter cannot be edited".to_string()),
+        _ => Err(format!("{value} has the wrong type for this parameter")),
+    }
+}
+
+/// Turns a number typed or dragged to into a JSON number, rounded to the precision of `step`
+pub fn number_value(number: f64, step: f64, integer: bool) -> Option<Value> {
+    if integer {
+        return Some(Value::Number(Number::from(number.round() as i64)));
+    }
+    let decimals = (-step.log10()).ceil().max(0.0) as usize;
+    let rounded: f64 = format!("{number:.decimals$}").parse().ok()?;
+
+    Number::from_f64(rounded).map(Value::Number)
+}
+
+/// Changes a parameter in the settings JSON of a preset after validating the new value. Only the
+/// value itself is rewritten, the rest of the text stays as it was.
+pub fn set_parameter(preset_data: &str, path: &str, value: &Value) -> Result<String, String> {
+    let settings: Value = serde_json::from_str(preset_data)
+        .map_err(|e| format!("The settings are not valid JSON: {e}"))?;
+    let current = path
+        .split('.')
+        .try_fold(&settings, |value, key| match value {
+            Value::Object(map) => map.get(key),
+            Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+            _ => None,
+        })
+        .ok_or_else(|| format!("The preset has no parameter {path}"))?;
+    validate_parameter(&parameter_control(path, current), value)?;
+
+    replace_value(preset_data, path, value)
+}
+
+/// A group of related settings of a preset
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterSection {
+    pub title: String,
+    pub parameters: Vec<Parameter>,
+}
+
+/// A single value in the settings of a preset
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    /// Keys leading to the value separated by dots, as used by [`crate::presets::sort::parameter_value`]
+    pub path: String,
+    pub value: Value,
+}
+
+/// Parses the settings JSON of a preset and groups its values into sections in the order the
+/// synth's subsystems are usually listed. Values keep the order they have in the file.
+pub fn parameter_sections(preset_data: &str) -> Result<Vec<ParameterSection>, String> {
+    let settings: Value = serde_json::from_str(preset_data)
+        .map_err(|e| format!("The settings are not valid JSON: {e}"))?;
+    let Value::Object(settings) = settings else {
+        return Err("The settings are not a JSON object".to_string());
+    };
+
+    let mut sections: Vec<ParameterSection> = ["General"]
+        .iter()
+        .chain(SECTIONS.iter().map(|(title, _)| title))
+        .map(|title| ParameterSection {
+            title: title.to_string(),
+            parameters: Vec::new(),
+        })
+        .collect();
+    for (key, value) in &settings {
+        flatten(key, value, &mut sections[section_index(key)].parameters);
+    }
+    sections.retain(|s| !s.parameters.is_empty());
+
+    Ok(sections)
+}
+
+/// The position of the section a top level settings key belongs in, 0 for "General"
+fn section_index(key: &str) -> usize {
+    let words = key_words(key);
+    let matches = |section_word: &&str| {
+        words
+            .iter()
+            .any(|word| word == section_word || word.strip_suffix('s') == Some(*section_word))
+    };
+
+    SECTIONS
+        .iter()
+        .position(|(_, section_words)| section_words.iter().any(matches))
+        .map_or(0, |i| i + 1)
+}
+
+/// Splits a settings key into lowercase words at underscores and other separators, between
+/// letters and digits and at camelCase boundaries, e.g. `osc2_LFORate` into `osc`, `2`, `lfo` and
+/// `rate`
+fn key_words(key: &str) -> Vec<String> {
+    let chars: Vec<char> = key.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        if let Some(&previous) = i.checked_sub(1).map(|p| &chars[p]) {
+            let next_is_lowercase = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            let boundary = (previous.is_lowercase() && c.is_uppercase())
+                || (previous.is_alphabetic() && c.is_numeric())
+                || (previous.is_numeric() && c.is_alphabetic())
+                // The last capital of an acronym starts the next word, as in `LFORate`
+                || (previous.is_uppercase() && c.is_uppercase() && next_is_lowercase);
+            if boundary && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+pub(crate) fn flatten(path: &str, value: &Value, parameters: &mut Vec<Parameter>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                flatten(&format!("{path}.{key}"), value, parameters);
+            }
+        }
+        Value::Array(items) if !items.is_empty() && !is_plain_list(items) => {
+            for (i, value) in items.iter().enumerate() {
+                flatten(&format!("{path}.{i}"), value, parameters);
+            }
+        }
+        _ => parameters.push(Parameter {
+            path: path.to_string(),
+            value: value.clone(),
+        }),
+    }
+}
+
+/// A long list of numbers or strings, like a wavetable, that reads better as a single value
+fn is_plain_list(items: &[Value]) -> bool {
+    items.len() > MAX_EXPANDED_ARRAY && items.iter().all(|v| !v.is_object() && !v.is_array())
+}
+
+/// Formats a parameter value for display, long lists are shortened
+pub fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(items) if items.len() > MAX_EXPANDED_ARRAY => {
+            let start: Vec<String> = items.iter().take(3).map(display_value).collect();
+            format!("[{}, …] ({} values)", start.join(", "), items.len())
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn section(key: &str) -> &'static str {
+        match section_index(key) {
+            0 => "General",
+            i => SECTIONS[i - 1].0,
+        }
+    }
+
+    #[test]
+    fn known_ranges_are_used_for_values_inside_them() {
+        assert_eq!(
+            parameter_control("filter.cutoff", &json!(0.25)),
+            ParameterControl::Slider {
+                min: 0.0,
+                max: 1.0,
+                step: 0.001
+            }
+        );
+        assert_eq!(
+            parameter_control("osc.0.Transpose", &json!(-7)),
+            ParameterControl::Slider {
+                min: -24.0,
+                max: 24.0,
+                step: 1.0
+            }
+        );
+    }
+
+    #[test]
+    fn whole_numbers_in_a_fractional_range_get_fine_steps() {
+        for value in [json!(1), json!(0)] {
+            assert_eq!(
+                parameter_control("mixer.level", &value),
+                ParameterControl::Slider {
+                    min: 0.0,
+                    max: 1.0,
+                    step: 0.001
+                }
+            );
+        }
+        assert_eq!(
+            parameter_control("octave", &json!(2.0)),
+            ParameterControl::Slider {
+                min: -4.0,
+                max: 4.0,
+                step: 1.0
+            }
+        );
+    }
+
+    #[test]
+    fn values_outside_their_known_range_get_a_number_field() {
+        assert_eq!(
+            parameter_control("filter.cutoff", &json!(8000)),
+            ParameterControl::Number { integer: true }
+        );
+        assert_eq!(
+            parameter_control("pan", &json!(-1.5)),
+            ParameterControl::Number { integer: false }
+        );
+    }
+
+    #[test]
+    fn fractions_without_a_known_range_get_a_number_field() {
+        assert_eq!(
+            parameter_control("env.attack", &json!(0.3)),
+            ParameterControl::Number { integer: false }
+        );
+    }
+
+    #[test]
+    fn keys_are_split_into_words() {
+        assert_eq!(key_words("osc2_LFORate"), ["osc", "2", "lfo", "rate"]);
+        assert_eq!(key_words("filterCutoff"), ["filter", "cutoff"]);
+        assert_eq!(key_words("amp-env.attack"), ["amp", "env", "attack"]);
+    }
+
+    #[test]
+    fn keys_are_sorted_into_sections_by_whole_words() {
+        assert_eq!(section("osc1_wave"), "Oscillators");
+        assert_eq!(section("ampEnvelope"), "Envelopes");
+        assert_eq!(section("filters"), "Filters");
+        assert_eq!(section("lfo2Rate"), "LFOs");
+        assert_eq!(section("mod_matrix"), "Modulation");
+        assert_eq!(section("reverbMix"), "Effects");
+        assert_eq!(section("masterVolume"), "Mixer");
+    }
+
+    #[test]
+    fn words_inside_other_words_do_not_match() {
+        for key in ["frequency", "sequencer", "mode", "sample", "expand"] {
+            assert_eq!(section(key), "General", "{key}");
+        }
+    }
+}
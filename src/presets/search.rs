@@ -0,0 +1,235 @@
+use crate::presets::sort::{compare_values, parameter_value, preset_name};
+use crate::types::LoadedPresetEntry;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// How a parameter is compared to the value in a query
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// Operators in the order they are looked for, so `>=` is not read as `>`
+const OPERATORS: [(&str, Comparison); 7] = [
+    (">=", Comparison::GreaterOrEqual),
+    ("<=", Comparison::LessOrEqual),
+    ("!=", Comparison::NotEqual),
+    ("==", Comparison::Equal),
+    (">", Comparison::Greater),
+    ("<", Comparison::Less),
+    ("=", Comparison::Equal),
+];
+
+impl Comparison {
+    fn holds(self, order: Ordering) -> bool {
+        match self {
+            Comparison::Equal => order == Ordering::Equal,
+            Comparison::NotEqual => order != Ordering::Equal,
+            Comparison::Less => order == Ordering::Less,
+            Comparison::LessOrEqual => order != Ordering::Greater,
+            Comparison::Greater => order == Ordering::Greater,
+            Comparison::GreaterOrEqual => order != Ordering::Less,
+        }
+    }
+}
+
+/// How a parameter value compares to a query value, `None` unless both are numbers, both are text
+/// or both are booleans. Unlike sorting, values of different types never match.
+fn compare_same_type(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64()?.partial_cmp(&y.as_f64()?),
+        (Value::String(_), Value::String(_)) | (Value::Bool(_), Value::Bool(_)) => {
+            Some(compare_values(a, b))
+        }
+        _ => None,
+    }
+}
+
+/// What to look for in a bank
+#[derive(Debug, Clone, PartialEq)]
+pub enum PresetQuery {
+    /// Text found in the name, a tag or a file name of a preset, ignoring case
+    Text(String),
+    /// A parameter compared to a value, written like `filter.cutoff > 0.5`
+    Parameter {
+        path: String,
+        comparison: Comparison,
+        value: Value,
+    },
+}
+
+impl PresetQuery {
+    /// Reads a query as typed into the search box, `None` when there is nothing to look for
+    pub fn parse(query: &str) -> Option<Self> {
+        let query = query.trim();
+        if query.is_empty() {
+            return None;
+        }
+
+        let parameter = OPERATORS.iter().find_map(|(operator, comparison)| {
+            let (path, value) = query.split_once(operator)?;
+            let (path, value) = (path.trim(), value.trim());
+            if path.is_empty() || path.contains(char::is_whitespace) || value.is_empty() {
+                return None;
+            }
+            // Values that are not JSON, like an unquoted word, are compared as text
+            let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.into()));
+            Some(PresetQuery::Parameter {
+                path: path.to_string(),
+                comparison: *comparison,
+                value,
+            })
+        });
+
+        Some(parameter.unwrap_or_else(|| PresetQuery::Text(query.to_lowercase())))
+    }
+
+    pub fn matches(&self, entry: &LoadedPresetEntry) -> bool {
+        match self {
+            PresetQuery::Text(text) => {
+                let contains = |field: &str| field.to_lowercase().contains(text);
+                preset_name(entry).is_some_and(|name| contains(&name))
+                    || contains(&entry.preset_filename)
+                    || contains(&entry.image_filename)
+                    || entry.metadata.tags.iter().any(|tag| contains(tag))
+            }
+            PresetQuery::Parameter {
+                path,
+                comparison,
+                value,
+            } => parameter_value(entry, path)
+                .and_then(|current| compare_same_type(&current, value))
+                .is_some_and(|order| comparison.holds(order)),
+        }
+    }
+}
+
+/// The slots of the presets matching a query, in order
+pub fn matching_presets(
+    presets: &HashMap<i32, LoadedPresetEntry>,
+    query: &PresetQuery,
+) -> Vec<i32> {
+    let mut indices: Vec<i32> = presets
+        .iter()
+        .filter(|(_, entry)| query.matches(entry))
+        .map(|(i, _)| *i)
+        .collect();
+    indices.sort();
+
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Side;
+    use serde_json::json;
+
+    fn entry(settings: Value) -> LoadedPresetEntry {
+        LoadedPresetEntry::new(
+            0,
+            Side::Left,
+            "image_timbre0_0.lsi".to_string(),
+            "settings_timbre0_0.json".to_string(),
+            Vec::new(),
+            settings.to_string(),
+            None,
+        )
+    }
+
+    fn matches(query: &str, settings: Value) -> bool {
+        PresetQuery::parse(query).unwrap().matches(&entry(settings))
+    }
+
+    fn parameter(path: &str, comparison: Comparison, value: Value) -> Option<PresetQuery> {
+        Some(PresetQuery::Parameter {
+            path: path.to_string(),
+            comparison,
+            value,
+        })
+    }
+
+    #[test]
+    fn blank_queries_look_for_nothing() {
+        assert_eq!(PresetQuery::parse(""), None);
+        assert_eq!(PresetQuery::parse("  \t"), None);
+    }
+
+    #[test]
+    fn plain_text_is_searched_ignoring_case() {
+        assert_eq!(PresetQuery::parse(" Warm Pad "), Some(PresetQuery::Text("warm pad".into())));
+    }
+
+    #[test]
+    fn comparisons_are_read_with_the_longest_operator() {
+        assert_eq!(
+            PresetQuery::parse("filter.cutoff >= 0.5"),
+            parameter("filter.cutoff", Comparison::GreaterOrEqual, json!(0.5))
+        );
+        assert_eq!(
+            PresetQuery::parse("octave!=-1"),
+            parameter("octave", Comparison::NotEqual, json!(-1))
+        );
+        assert_eq!(
+            PresetQuery::parse("mono == true"),
+            parameter("mono", Comparison::Equal, json!(true))
+        );
+        assert_eq!(
+            PresetQuery::parse("voices<4"),
+            parameter("voices", Comparison::Less, json!(4))
+        );
+    }
+
+    #[test]
+    fn values_that_are_not_json_are_text() {
+        assert_eq!(
+            PresetQuery::parse("waveform = saw"),
+            parameter("waveform", Comparison::Equal, json!("saw"))
+        );
+    }
+
+    #[test]
+    fn incomplete_comparisons_are_text() {
+        assert_eq!(PresetQuery::parse("cutoff >"), Some(PresetQuery::Text("cutoff >".into())));
+        assert_eq!(PresetQuery::parse("= 3"), Some(PresetQuery::Text("= 3".into())));
+        assert_eq!(
+            PresetQuery::parse("warm pad > 3"),
+            Some(PresetQuery::Text("warm pad > 3".into()))
+        );
+    }
+
+    #[test]
+    fn text_matches_the_name_and_file_names() {
+        assert!(matches("warm", json!({"name": "Warm Pad"})));
+        assert!(matches("timbre0_0", json!({})));
+        assert!(!matches("bell", json!({"name": "Warm Pad"})));
+    }
+
+    #[test]
+    fn parameters_are_compared_to_values_of_the_same_type() {
+        let settings = json!({"filter": {"cutoff": 0.8}, "mono": true, "waveform": "saw"});
+        assert!(matches("filter.cutoff > 0.5", settings.clone()));
+        assert!(matches("filter.cutoff <= 0.8", settings.clone()));
+        assert!(!matches("filter.cutoff < 0.5", settings.clone()));
+        assert!(matches("mono = true", settings.clone()));
+        assert!(matches("waveform != square", settings.clone()));
+        assert!(!matches("waveform = square", settings.clone()));
+        assert!(!matches("resonance > 0", settings));
+    }
+
+    #[test]
+    fn values_of_another_type_never_match() {
+        for cutoff in [json!("high"), json!([1]), json!({"value": 1}), json!(null), json!(false)] {
+            let settings = json!({ "cutoff": cutoff });
+            for query in ["cutoff > 0.5", "cutoff < 0.5", "cutoff = 0.5", "cutoff != 0.5"] {
+                assert!(!matches(query, settings.clone()), "{query} with {settings}");
+            }
+        }
+    }
+}
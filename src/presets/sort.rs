@@ -0,0 +1,191 @@
+use crate::types::LoadedPresetEntry;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::SystemTime;
+
+/// What to sort the presets of a bank by
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SortKey {
+    /// The name given in the preset's metadata, or else the `name` field of the settings JSON
+    Name,
+    /// The slot the preset was loaded from
+    OriginalIndex,
+    /// Modification time of the settings file
+    Modified,
+    /// A value in the settings JSON, given as a path of keys separated by dots like `filter.cutoff`
+    Parameter(String),
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortKey::Name => f.write_str("name"),
+            SortKey::OriginalIndex => f.write_str("original index"),
+            SortKey::Modified => f.write_str("modification date"),
+            SortKey::Parameter(path) => write!(f, "{path}"),
+        }
+    }
+}
+
+/// The name of a preset from its metadata or the `name` field of its settings, if it has one
+pub fn preset_name(entry: &LoadedPresetEntry) -> Option<String> {
+    if !entry.metadata.name.is_empty() {
+        return Some(entry.metadata.name.clone());
+    }
+    match parameter_value(entry, "name")? {
+        Value::String(name) => Some(name),
+        _ => None,
+    }
+}
+
+/// Looks up a value in a preset's settings by a path of keys separated by dots. Array elements
+/// are addressed by their index, e.g. `envelopes.0.attack`.
+pub fn parameter_value(entry: &LoadedPresetEntry, path: &str) -> Option<Value> {
+    let settings: Value = serde_json::from_str(&entry.preset_data).ok()?;
+    let mut value = &settings;
+    for key in path.split('.') {
+        value = match value {
+            Value::Object(map) => map.get(key)?,
+            Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+
+    Some(value.clone())
+}
+
+/// Orders numbers numerically and strings case-insensitively, values of different types are
+/// ordered by type
+pub fn compare_values(a: &Value, b: &Value) -> Ordering {
+    fn type_rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(a), Value::String(b)) => compare_text(a, b),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        _ => type_rank(a)
+            .cmp(&type_rank(b))
+            .then_with(|| a.to_string().cmp(&b.to_string())),
+    }
+}
+
+fn compare_text(a: &str, b: &str) -> Ordering {
+    a.to_lowercase()
+        .cmp(&b.to_lowercase())
+        .then_with(|| a.cmp(b))
+}
+
+/// The value of a sort key for one preset
+#[derive(Debug)]
+enum KeyValue {
+    Name(String),
+    OriginalIndex(i32, u8),
+    Modified(SystemTime),
+    Parameter(Value),
+}
+
+impl KeyValue {
+    /// Reads the value of `key` from a preset, `None` when the preset has none
+    fn of(entry: &LoadedPresetEntry, key: &SortKey) -> Option<KeyValue> {
+        Some(match key {
+            SortKey::Name => KeyValue::Name(preset_name(entry)?),
+            SortKey::OriginalIndex => {
+                KeyValue::OriginalIndex(entry.original_index, entry.original_side as u8)
+            }
+            SortKey::Modified => KeyValue::Modified(entry.modified?),
+            SortKey::Parameter(path) => KeyValue::Parameter(parameter_value(entry, path)?),
+        })
+    }
+
+    fn compare(&self, other: &KeyValue) -> Ordering {
+        match (self, other) {
+            (KeyValue::Name(a), KeyValue::Name(b)) => compare_text(a, b),
+            (KeyValue::OriginalIndex(a, a_side), KeyValue::OriginalIndex(b, b_side)) => {
+                a.cmp(b).then_with(|| a_side.cmp(b_side))
+            }
+            (KeyValue::Modified(a), KeyValue::Modified(b)) => a.cmp(b),
+            (KeyValue::Parameter(a), KeyValue::Parameter(b)) => compare_values(a, b),
+            // Values of one sort key are always of the same kind
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+/// A key value ordered the way the presets are sorted
+#[derive(Debug)]
+struct SortValue {
+    value: KeyValue,
+    descending: bool,
+}
+
+impl Ord for SortValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let order = self.value.compare(&other.value);
+        if self.descending {
+            order.reverse()
+        } else {
+            order
+        }
+    }
+}
+
+impl PartialOrd for SortValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SortValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SortValue {}
+
+/// Returns the bank with the presets in `slots` rearranged among those same slots, sorted by `key`.
+/// Presets without a value for the key go last in their current order, presets outside `slots`
+/// stay where they are.
+pub fn sorted_presets(
+    presets: &HashMap<i32, LoadedPresetEntry>,
+    slots: &[i32],
+    key: &SortKey,
+    descending: bool,
+) -> HashMap<i32, LoadedPresetEntry> {
+    let mut slots: Vec<i32> = slots
+        .iter()
+        .copied()
+        .filter(|i| presets.contains_key(i))
+        .collect();
+    slots.sort();
+    slots.dedup();
+
+    // The settings are parsed once per preset rather than for every comparison. The sort is
+    // stable, so presets with equal values and those without a value keep their order.
+    let mut sources = slots.clone();
+    sources.sort_by_cached_key(|i| {
+        let value = KeyValue::of(&presets[i], key).map(|value| SortValue { value, descending });
+        (value.is_none(), value)
+    });
+
+    let mut result = presets.clone();
+    for (slot, source) in slots.iter().zip(&sources) {
+        result.insert(*slot, presets[source].clone());
+    }
+
+    result
+}
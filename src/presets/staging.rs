@@ -0,0 +1,223 @@
+use crate::presets::error::BankError;
+use crate::presets::{
+    SYSTEM_AUTOSAVE_FILE, SYSTEM_IMAGE_FILE_PREFIX, SYSTEM_METADATA_FILE,
+    SYSTEM_PRESET_FILE_PREFIX, SYSTEM_PRESET_LIST_FILE, TIMBRE_IMAGE_FILE_PREFIX,
+    TIMBRE_METADATA_FILE, TIMBRE_PRESET_FILE_PREFIX, TIMBRE_PRESET_LIST_FILE,
+};
+use crate::types::PresetType;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// A save first writes the whole bank into a staging directory inside the bank folder. Only once
+// that has fully succeeded are the current bank files moved aside and the staged files moved in.
+// Both directories get a marker file when their step is complete, so a save that was interrupted
+// by a crash can be finished by `recover_interrupted_save` the next time the folder is opened.
+const STAGING_DIR_PREFIX: &str = ".preset_manager_staging";
+const REPLACED_DIR_PREFIX: &str = ".preset_manager_replaced";
+const COMPLETE_MARKER: &str = ".complete";
+
+fn type_suffix(preset_type: PresetType) -> &'static str {
+    match preset_type {
+        PresetType::Timbre => "timbre",
+        PresetType::System => "system",
+    }
+}
+
+fn staging_dir(path: &str, preset_type: PresetType) -> PathBuf {
+    Path::new(path).join(format!("{STAGING_DIR_PREFIX}_{}", type_suffix(preset_type)))
+}
+
+fn replaced_dir(path: &str, preset_type: PresetType) -> PathBuf {
+    Path::new(path).join(format!("{REPLACED_DIR_PREFIX}_{}", type_suffix(preset_type)))
+}
+
+/// Whether a file in a bank folder belongs to the bank of the given type and is replaced on save
+pub fn is_bank_file(file_name: &str, preset_type: PresetType) -> bool {
+    let (image_prefix, preset_prefix, preset_list, metadata) = match preset_type {
+        PresetType::Timbre => (
+            TIMBRE_IMAGE_FILE_PREFIX,
+            TIMBRE_PRESET_FILE_PREFIX,
+            TIMBRE_PRESET_LIST_FILE,
+            TIMBRE_METADATA_FILE,
+        ),
+        PresetType::System => (
+            SYSTEM_IMAGE_FILE_PREFIX,
+            SYSTEM_PRESET_FILE_PREFIX,
+            SYSTEM_PRESET_LIST_FILE,
+            SYSTEM_METADATA_FILE,
+        ),
+    };
+
+    (file_name.starts_with(image_prefix)
+        || file_name.starts_with(preset_prefix)
+        || file_name == preset_list
+        || file_name == metadata)
+        && file_name != SYSTEM_AUTOSAVE_FILE
+}
+
+/// Writes a file and flushes it to disk before returning
+pub fn write_synced(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<(), BankError> {
+    let path = path.as_ref();
+    let mut file = fs::File::create(path).map_err(|e| BankError::io("create", path, e))?;
+    file.write_all(contents.as_ref())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| BankError::io("write", path, e))?;
+
+    Ok(())
+}
+
+/// Creates an empty staging directory for a new version of the bank and returns its path
+pub fn begin_staging(path: &str, preset_type: PresetType) -> Result<PathBuf, BankError> {
+    let staging = staging_dir(path, preset_type);
+    if staging.exists() {
+        fs::remove_dir_all(&staging).map_err(|e| BankError::io("remove", &staging, e))?;
+    }
+    fs::create_dir(&staging).map_err(|e| BankError::io("create", &staging, e))?;
+
+    Ok(staging)
+}
+
+/// Throws away a staging directory without touching the bank
+pub fn abort_staging(path: &str, preset_type: PresetType) {
+    let _ = fs::remove_dir_all(staging_dir(path, preset_type));
+}
+
+/// Replaces the bank files in `path` with the files in the staging directory. On failure every
+/// file is moved back, so the folder is left exactly as it was before the save.
+pub fn commit_staging(path: &str, preset_type: PresetType) -> Result<(), BankError> {
+    let staging = staging_dir(path, preset_type);
+    let replaced = replaced_dir(path, preset_type);
+
+    if let Err(e) = write_synced(staging.join(COMPLETE_MARKER), "") {
+        abort_staging(path, preset_type);
+        return Err(e);
+    }
+
+    let mut moved_out = Vec::new();
+    let move_out_result = fs::create_dir(&replaced)
+        .map_err(|e| BankError::io("create", &replaced, e))
+        .and_then(|_| move_bank_files(Path::new(path), &replaced, preset_type, &mut moved_out))
+        .and_then(|_| write_synced(replaced.join(COMPLETE_MARKER), ""));
+    if let Err(e) = move_out_result {
+        undo_moves(&moved_out);
+        let _ = fs::remove_dir_all(&replaced);
+        abort_staging(path, preset_type);
+        let e = e.in_folder(&replaced, Path::new(path));
+        return Err(BankError::SaveAborted(Box::new(e)));
+    }
+
+    let mut moved_in = Vec::new();
+    if let Err(e) = move_staged_files(&staging, Path::new(path), &mut moved_in) {
+        undo_moves(&moved_in);
+        undo_moves(&moved_out);
+        let _ = fs::remove_dir_all(&replaced);
+        abort_staging(path, preset_type);
+        let e = e.in_folder(&staging, Path::new(path));
+        return Err(BankError::SaveAborted(Box::new(e)));
+    }
+
+    // The new bank is in place at this point, leftovers are cleaned up by the next recovery
+    let _ = fs::remove_dir_all(&staging);
+    let _ = fs::remove_dir_all(&replaced);
+
+    Ok(())
+}
+
+/// Whether a save was interrupted after the new bank was fully written, so the bank files in the
+/// folder may be partly replaced until [`recover_interrupted_save`] finishes the save
+pub fn has_interrupted_save(path: &str, preset_type: PresetType) -> bool {
+    staging_dir(path, preset_type).join(COMPLETE_MARKER).exists()
+}
+
+/// Finishes or rolls back a save that was interrupted, e.g. by a crash or power loss
+pub fn recover_interrupted_save(path: &str, preset_type: PresetType) -> Result<(), BankError> {
+    let staging = staging_dir(path, preset_type);
+    let replaced = replaced_dir(path, preset_type);
+
+    if !staging.exists() {
+        // Only the cleanup of a finished save was interrupted
+        if replaced.exists() {
+            fs::remove_dir_all(&replaced).map_err(|e| BankError::io("remove", &replaced, e))?;
+        }
+        return Ok(());
+    }
+
+    if !staging.join(COMPLETE_MARKER).exists() {
+        // The new bank was never fully written, the original files have not been touched
+        fs::remove_dir_all(&staging).map_err(|e| BankError::io("remove", &staging, e))?;
+        return Ok(());
+    }
+
+    // The new bank was fully written, so roll the save forward
+    if !replaced.join(COMPLETE_MARKER).exists() {
+        if !replaced.exists() {
+            fs::create_dir(&replaced).map_err(|e| BankError::io("create", &replaced, e))?;
+        }
+        move_bank_files(Path::new(path), &replaced, preset_type, &mut Vec::new())?;
+        write_synced(replaced.join(COMPLETE_MARKER), "")?;
+    }
+    move_staged_files(&staging, Path::new(path), &mut Vec::new())?;
+
+    fs::remove_dir_all(&staging).map_err(|e| BankError::io("remove", &staging, e))?;
+    fs::remove_dir_all(&replaced).map_err(|e| BankError::io("remove", &replaced, e))?;
+
+    Ok(())
+}
+
+fn move_bank_files(
+    from: &Path,
+    to: &Path,
+    preset_type: PresetType,
+    moved: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<(), BankError> {
+    let read_error = |e| BankError::io("read", from, e);
+    for entry in fs::read_dir(from).map_err(read_error)? {
+        let entry = entry.map_err(read_error)?;
+        let file_name = match entry.file_name().into_string() {
+            Ok(file_name) => file_name,
+            // Ignore files with invalid unicode filenames since we will never write to them
+            Err(_) => continue,
+        };
+
+        if is_bank_file(&file_name, preset_type) {
+            move_file(&entry.path(), &to.join(&file_name), moved)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn move_staged_files(
+    staging: &Path,
+    to: &Path,
+    moved: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<(), BankError> {
+    let read_error = |e| BankError::io("read", staging, e);
+    for entry in fs::read_dir(staging).map_err(read_error)? {
+        let entry = entry.map_err(read_error)?;
+        if entry.file_name() == COMPLETE_MARKER {
+            continue;
+        }
+        move_file(&entry.path(), &to.join(entry.file_name()), moved)?;
+    }
+
+    Ok(())
+}
+
+fn move_file(
+    from: &Path,
+    to: &Path,
+    moved: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<(), BankError> {
+    fs::rename(from, to).map_err(|e| BankError::io("move", from, e))?;
+    moved.push((from.to_path_buf(), to.to_path_buf()));
+
+    Ok(())
+}
+
+fn undo_moves(moved: &[(PathBuf, PathBuf)]) {
+    for (from, to) in moved.iter().rev() {
+        let _ = fs::rename(to, from);
+    }
+}
@@ -0,0 +1,469 @@
+use crate::presets::backup::backup_bank;
+use crate::presets::error::BankError;
+use crate::presets::lsi_parser::parse_lsi_image;
+use crate::presets::metadata::load_metadata;
+use crate::presets::staging::{has_interrupted_save, recover_interrupted_save};
+use crate::presets::{
+    preset_filename, preset_filename_location, preset_image_filename, preset_index_from_location,
+    read_image, read_preset_list, read_settings, save_presets, SYSTEM_AUTOSAVE_FILE,
+    SYSTEM_IMAGE_FILE_PREFIX, SYSTEM_PRESET_FILE_PREFIX, SYSTEM_PRESET_LIST_FILE,
+    TIMBRE_IMAGE_FILE_PREFIX, TIMBRE_PRESET_FILE_PREFIX, TIMBRE_PRESET_LIST_FILE,
+};
+use crate::types::{ImageData, LoadedPresetEntry, PresetType, Side};
+use anyhow::{bail, Context};
+use serde_json::Value;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Something wrong with a bank folder
+#[derive(Debug, Clone, PartialEq)]
+pub enum BankProblem {
+    /// The preset list cannot be read, nothing else can be checked
+    UnreadableList(String),
+    /// A save was interrupted, the bank files are checked once it is finished
+    InterruptedSave(String),
+    /// The metadata file cannot be read, the presets are checked without it
+    UnreadableMetadata(String),
+    MissingFile { index: i32, file: String },
+    /// A file that is there but cannot be read, e.g. for lack of permission or as text
+    UnreadableFile { index: i32, file: String, error: String },
+    InvalidSettings { index: i32, file: String, error: String },
+    InvalidImage { index: i32, file: String, error: String },
+    /// A later entry of the list uses a slot that an earlier one already has
+    DuplicateIndex { index: i32, file: String },
+    /// The file names of an entry do not match its slot
+    MisnamedFiles { index: i32, file: String },
+    /// A settings or image file of the bank that the list does not refer to
+    OrphanFile { file: String },
+}
+
+impl fmt::Display for BankProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let slot = |index: &i32| preset_filename_location(*index);
+        match self {
+            BankProblem::UnreadableList(error) => {
+                write!(f, "The preset list cannot be read: {error}")
+            }
+            BankProblem::InterruptedSave(error) => f.write_str(error),
+            BankProblem::UnreadableMetadata(error) => {
+                write!(f, "The metadata file cannot be read: {error}")
+            }
+            BankProblem::MissingFile { index, file } => {
+                write!(f, "Slot {}: {file} is missing", slot(index))
+            }
+            BankProblem::UnreadableFile { index, file, error } => {
+                write!(f, "Slot {}: {file} cannot be read: {error}", slot(index))
+            }
+            BankProblem::InvalidSettings { index, file, error } => {
+                write!(f, "Slot {}: {file} is not valid JSON: {error}", slot(index))
+            }
+            BankProblem::InvalidImage { index, file, error } => {
+                write!(f, "Slot {}: {file} is not a valid image: {error}", slot(index))
+            }
+            BankProblem::DuplicateIndex { index, file } => {
+                write!(f, "Slot {}: {file} uses a slot that is already taken", slot(index))
+            }
+            BankProblem::MisnamedFiles { index, file } => {
+                write!(f, "Slot {}: {file} is named after another slot", slot(index))
+            }
+            BankProblem::OrphanFile { file } => write!(f, "{file} is not in the preset list"),
+        }
+    }
+}
+
+/// What a repair does to a bank
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Repair {
+    /// Leave out the entries whose files are missing or broken
+    DropBroken,
+    /// Add the presets of settings files that are not in the list
+    AdoptOrphans,
+    /// Move presets that share a slot with another one to free slots
+    Renumber,
+    /// Name the files of every preset after its slot
+    Rename,
+}
+
+impl Repair {
+    pub const ALL: [Repair; 4] = [
+        Repair::DropBroken,
+        Repair::AdoptOrphans,
+        Repair::Renumber,
+        Repair::Rename,
+    ];
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Repair::DropBroken => "Drop broken entries",
+            Repair::AdoptOrphans => "Adopt files missing from the list",
+            Repair::Renumber => "Renumber presets that share a slot",
+            Repair::Rename => "Rename files named after another slot",
+        })
+    }
+}
+
+/// The result of checking a bank folder
+#[derive(Debug, Clone)]
+pub struct BankCheck {
+    pub path: String,
+    pub preset_type: PresetType,
+    pub problems: Vec<BankProblem>,
+    /// The presets that loaded, keyed by slot
+    pub presets: HashMap<i32, LoadedPresetEntry>,
+    /// Presets that loaded but whose slot was taken by an earlier entry of the list
+    pub displaced: Vec<LoadedPresetEntry>,
+    /// Presets read from settings files that are not in the list, with the slot their names give
+    pub orphans: Vec<(Option<i32>, LoadedPresetEntry)>,
+    /// Entries of the list left out because their files are missing or broken
+    pub broken: usize,
+    /// Entries of the list whose files are named after another slot
+    pub misnamed: usize,
+    /// Files that are not in the list and cannot be adopted, such as an image without settings
+    pub stray: Vec<String>,
+}
+
+impl BankCheck {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Whether the list could be read, without it there is nothing to repair
+    pub fn is_repairable(&self) -> bool {
+        !self
+            .problems
+            .iter()
+            .any(|p| matches!(p, BankProblem::UnreadableList(_)))
+    }
+
+    /// The repairs that would change something in this bank
+    pub fn applicable_repairs(&self) -> Vec<Repair> {
+        Repair::ALL
+            .into_iter()
+            .filter(|repair| match repair {
+                Repair::DropBroken => self.broken > 0,
+                Repair::AdoptOrphans => !self.orphans.is_empty(),
+                Repair::Renumber => !self.displaced.is_empty(),
+                Repair::Rename => self.misnamed > 0,
+            })
+            .collect()
+    }
+}
+
+fn file_prefixes(preset_type: PresetType) -> (&'static str, &'static str, &'static str) {
+    match preset_type {
+        PresetType::Timbre => (
+            TIMBRE_PRESET_FILE_PREFIX,
+            TIMBRE_IMAGE_FILE_PREFIX,
+            TIMBRE_PRESET_LIST_FILE,
+        ),
+        PresetType::System => (
+            SYSTEM_PRESET_FILE_PREFIX,
+            SYSTEM_IMAGE_FILE_PREFIX,
+            SYSTEM_PRESET_LIST_FILE,
+        ),
+    }
+}
+
+/// The problem with a file of the preset in slot `index` that could not be read
+fn file_problem(index: i32, file: &str, error: BankError) -> BankProblem {
+    let file = file.to_string();
+    match error {
+        BankError::MissingFile { .. } => BankProblem::MissingFile { index, file },
+        BankError::Json {
+            line,
+            column,
+            message,
+            ..
+        } => BankProblem::InvalidSettings {
+            index,
+            file,
+            error: format!("{message} at line {line} column {column}"),
+        },
+        BankError::Image { error, .. } => BankProblem::InvalidImage {
+            index,
+            file,
+            error: error.to_string(),
+        },
+        error => BankProblem::UnreadableFile {
+            index,
+            file,
+            error: error.to_string(),
+        },
+    }
+}
+
+/// Reads the settings and image of a preset like the editor does, adding a problem for every
+/// file that is missing or broken
+fn read_files(
+    path: &Path,
+    index: i32,
+    preset_filename: &str,
+    image_filename: &str,
+    problems: &mut Vec<BankProblem>,
+) -> Option<(String, ImageData)> {
+    let (_, settings) = read_settings(&path.join(preset_filename));
+    let (_, image) = read_image(&path.join(image_filename));
+    let settings = settings
+        .map_err(|e| problems.push(file_problem(index, preset_filename, e)))
+        .ok();
+    let image = image
+        .map_err(|e| problems.push(file_problem(index, image_filename, e)))
+        .ok();
+
+    Some((settings?, image?))
+}
+
+/// Loads a bank leniently and lists every problem with it instead of stopping at the first one.
+/// Nothing in the folder is changed, a save that was interrupted is only reported.
+pub fn check_bank(path: &str, preset_type: PresetType) -> BankCheck {
+    let mut check = BankCheck {
+        path: path.to_string(),
+        preset_type,
+        problems: Vec::new(),
+        presets: HashMap::new(),
+        displaced: Vec::new(),
+        orphans: Vec::new(),
+        broken: 0,
+        misnamed: 0,
+        stray: Vec::new(),
+    };
+    let (preset_prefix, image_prefix, list_file) = file_prefixes(preset_type);
+    let folder = Path::new(path);
+
+    if has_interrupted_save(path, preset_type) {
+        let error = BankError::InterruptedSave {
+            path: folder.to_path_buf(),
+        };
+        check.problems.push(BankProblem::InterruptedSave(error.to_string()));
+        return check;
+    }
+    let list_path = folder.join(list_file);
+    // Read in order, so like on load the first entry of a slot keeps it
+    let list = match read_preset_list(&list_path) {
+        Ok(list) => list,
+        Err(e) => {
+            check.problems.push(BankProblem::UnreadableList(format!("{e:#}")));
+            return check;
+        }
+    };
+    let mut metadata = load_metadata(path, preset_type).unwrap_or_else(|e| {
+        check.problems.push(BankProblem::UnreadableMetadata(format!("{e:#}")));
+        HashMap::new()
+    });
+
+    let mut listed_files = HashSet::new();
+    for p in list {
+        listed_files.insert(p.preset_filename.clone());
+        listed_files.insert(p.image_filename.clone());
+        let Some((settings, image)) = read_files(
+            folder,
+            p.index,
+            &p.preset_filename,
+            &p.image_filename,
+            &mut check.problems,
+        ) else {
+            check.broken += 1;
+            continue;
+        };
+
+        if p.preset_filename != preset_filename(p.index, preset_type)
+            || p.image_filename != preset_image_filename(p.index, preset_type)
+        {
+            check.misnamed += 1;
+            check.problems.push(BankProblem::MisnamedFiles {
+                index: p.index,
+                file: p.preset_filename.clone(),
+            });
+        }
+        let modified = fs::metadata(folder.join(&p.preset_filename))
+            .and_then(|m| m.modified())
+            .ok();
+        let mut entry = LoadedPresetEntry::new(
+            p.index,
+            Side::Left,
+            p.image_filename,
+            p.preset_filename.clone(),
+            image,
+            settings,
+            modified,
+        );
+        entry.metadata = metadata.remove(&p.preset_filename).unwrap_or_default();
+
+        match check.presets.entry(p.index) {
+            Entry::Occupied(_) => {
+                check.problems.push(BankProblem::DuplicateIndex {
+                    index: p.index,
+                    file: p.preset_filename,
+                });
+                check.displaced.push(entry);
+            }
+            Entry::Vacant(slot) => {
+                slot.insert(entry);
+            }
+        }
+    }
+
+    let mut orphans: Vec<String> = fs::read_dir(folder)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.starts_with(preset_prefix) || name.starts_with(image_prefix))
+        .filter(|name| name != SYSTEM_AUTOSAVE_FILE && !listed_files.contains(name))
+        .collect();
+    orphans.sort();
+    for file in &orphans {
+        check.problems.push(BankProblem::OrphanFile { file: file.clone() });
+    }
+
+    // Only settings files can be adopted, an image alone is not a preset
+    let mut adopted_files = HashSet::new();
+    for file in orphans.iter().filter(|name| name.starts_with(preset_prefix)) {
+        let location = file
+            .strip_prefix(preset_prefix)
+            .and_then(|rest| rest.strip_suffix(".json"));
+        let index = location.and_then(preset_index_from_location);
+        let image_filename = format!("{image_prefix}{}.lsi", location.unwrap_or_default());
+        let Ok(settings) = fs::read_to_string(folder.join(file)) else {
+            continue;
+        };
+        if serde_json::from_str::<Value>(&settings).is_err() {
+            continue;
+        }
+        // A missing or broken image is replaced with an empty one
+        let image = fs::read(folder.join(&image_filename))
+            .ok()
+            .and_then(|data| parse_lsi_image(&data).ok())
+            .unwrap_or_default();
+        let modified = fs::metadata(folder.join(file)).and_then(|m| m.modified()).ok();
+        let mut entry = LoadedPresetEntry::new(
+            index.unwrap_or(-1),
+            Side::Left,
+            image_filename,
+            file.clone(),
+            image,
+            settings,
+            modified,
+        );
+        entry.metadata = metadata.remove(file).unwrap_or_default();
+        adopted_files.insert(entry.preset_filename.clone());
+        adopted_files.insert(entry.image_filename.clone());
+        check.orphans.push((index, entry));
+    }
+    check.stray = orphans
+        .into_iter()
+        .filter(|file| !adopted_files.contains(file))
+        .collect();
+
+    check
+}
+
+/// How a repair changed a bank
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RepairSummary {
+    pub dropped: usize,
+    pub adopted: usize,
+    pub renumbered: usize,
+    pub renamed: usize,
+    /// Stray files that are no longer in the bank, only in its backup
+    pub left_out: usize,
+}
+
+impl fmt::Display for RepairSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "dropped {} broken, adopted {}, renumbered {} and renamed {} presets",
+            self.dropped, self.adopted, self.renumbered, self.renamed
+        )?;
+        if self.left_out > 0 {
+            write!(f, ", left out {} stray files that are only in the backup now", self.left_out)?;
+        }
+        Ok(())
+    }
+}
+
+/// The presets of a checked bank after the given repairs. Saving a bank writes only the presets
+/// in its list, so every problem that would lose a preset has to be repaired. Stray files that
+/// cannot be adopted are left out, they are kept in the backup made before the repair.
+pub fn repaired_presets(
+    check: &BankCheck,
+    repairs: &[Repair],
+) -> Result<(HashMap<i32, LoadedPresetEntry>, RepairSummary), String> {
+    if !check.is_repairable() {
+        return Err("The preset list cannot be read, there is nothing to repair".to_string());
+    }
+    if check.broken > 0 && !repairs.contains(&Repair::DropBroken) {
+        return Err("Entries with missing or broken files can only be dropped".to_string());
+    }
+    if !check.displaced.is_empty() && !repairs.contains(&Repair::Renumber) {
+        return Err("Presets that share a slot are lost unless they are renumbered".to_string());
+    }
+    if !check.orphans.is_empty() && !repairs.contains(&Repair::AdoptOrphans) {
+        return Err("Presets missing from the list are lost unless they are adopted".to_string());
+    }
+    // Saving names every file after its slot, so misnamed files cannot be kept as they are
+    if check.misnamed > 0 && !repairs.contains(&Repair::Rename) {
+        return Err("Files named after another slot are renamed by every save".to_string());
+    }
+
+    let mut presets = check.presets.clone();
+    let mut summary = RepairSummary {
+        dropped: check.broken,
+        renamed: check.misnamed,
+        left_out: check.stray.len(),
+        ..RepairSummary::default()
+    };
+    let mut next_free = 0;
+    let mut free_slot = |presets: &HashMap<i32, LoadedPresetEntry>| {
+        while presets.contains_key(&next_free) {
+            next_free += 1;
+        }
+        next_free
+    };
+
+    if repairs.contains(&Repair::Renumber) {
+        for entry in &check.displaced {
+            let slot = free_slot(&presets);
+            presets.insert(slot, entry.clone());
+            summary.renumbered += 1;
+        }
+    }
+    if repairs.contains(&Repair::AdoptOrphans) {
+        for (index, entry) in &check.orphans {
+            let slot = match index {
+                Some(index) if !presets.contains_key(index) => *index,
+                _ => free_slot(&presets),
+            };
+            presets.insert(slot, entry.clone());
+            summary.adopted += 1;
+        }
+    }
+
+    Ok((presets, summary))
+}
+
+/// Finishes an interrupted save, checks the bank, applies the repairs and saves it after backing
+/// it up
+pub fn repair_bank(
+    path: &str,
+    preset_type: PresetType,
+    repairs: &[Repair],
+) -> anyhow::Result<RepairSummary> {
+    recover_interrupted_save(path, preset_type)?;
+    let check = check_bank(path, preset_type);
+    let (presets, summary) = match repaired_presets(&check, repairs) {
+        Ok(repaired) => repaired,
+        Err(message) => bail!(message),
+    };
+
+    backup_bank(path, preset_type)
+        .with_context(|| format!("Cannot back up {path}, nothing was repaired"))?;
+    save_presets(path, &presets, preset_type)?;
+
+    Ok(summary)
+}
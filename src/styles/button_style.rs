@@ -12,10 +12,16 @@ impl GeneralButtonStyle {
     }
 }
 
+impl Default for GeneralButtonStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl button::StyleSheet for GeneralButtonStyle {
     type Style = Theme;
 
-    fn active(&self, style: &Self::Style) -> Appearance {
+    fn active(&self, _style: &Self::Style) -> Appearance {
         Appearance {
             shadow_offset: Default::default(),
             background: Some(Background::Color(PRIMARY_COLOR)),
@@ -1,2 +1,2 @@
 pub mod button_style;
-pub mod preset_button_style;
\ No newline at end of file
+pub mod preset_slot_style;
\ No newline at end of file
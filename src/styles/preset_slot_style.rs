@@ -0,0 +1,70 @@
+use crate::colors::{
+    BROKEN_PRESET_BACKGROUND_COLOR, BROKEN_PRESET_COLOR, DROP_TARGET_COLOR,
+    HIGHLIGHT_BACKGROUND_COLOR, PRESET_COLOR, PRESET_HOVER_COLOR, PRESET_SELECTED_COLOR,
+};
+use iced::border::Radius;
+use iced::widget::container;
+use iced::widget::container::Appearance;
+use iced::{Background, Border, Color, Theme};
+
+#[derive(Default)]
+pub struct PresetSlotStyle {
+    pub selected: bool,
+    pub hovered: bool,
+    // Another preset is being dragged over this slot
+    pub drop_target: bool,
+    // This slot's preset is being dragged
+    pub dragged: bool,
+    // The slot does not match the search
+    pub dimmed: bool,
+    // The keyboard cursor is on this slot
+    pub cursor: bool,
+    // The slot's preset could not be loaded
+    pub broken: bool,
+}
+
+impl container::StyleSheet for PresetSlotStyle {
+    type Style = Theme;
+
+    fn appearance(&self, _style: &Self::Style) -> Appearance {
+        let (width, color) = if self.drop_target {
+            (2.0, DROP_TARGET_COLOR)
+        } else if self.cursor {
+            (2.0, Color::WHITE)
+        } else if self.hovered {
+            (1.0, PRESET_HOVER_COLOR)
+        } else if self.selected || self.dragged {
+            (2.0, PRESET_SELECTED_COLOR)
+        } else if self.broken {
+            (1.0, BROKEN_PRESET_COLOR)
+        } else if self.dimmed {
+            (
+                1.0,
+                Color {
+                    a: 0.25,
+                    ..PRESET_COLOR
+                },
+            )
+        } else {
+            (1.0, PRESET_COLOR)
+        };
+
+        let background = if self.hovered || self.drop_target || self.dragged {
+            Some(Background::Color(HIGHLIGHT_BACKGROUND_COLOR))
+        } else {
+            self.broken
+                .then_some(Background::Color(BROKEN_PRESET_BACKGROUND_COLOR))
+        };
+
+        Appearance {
+            text_color: Some(Color::WHITE),
+            background,
+            border: Border {
+                radius: Radius::from(8.0),
+                width,
+                color,
+            },
+            shadow: Default::default(),
+        }
+    }
+}
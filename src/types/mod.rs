@@ -1,8 +1,11 @@
 use std::collections::HashMap;
+use std::time::SystemTime;
 use serde::{Deserialize, Serialize};
 
+/// The lines of a preset drawing, coordinates are between 0 and 1
 pub type ImageData = Vec<Vec<PointF>>;
 
+/// An entry of a preset list file as stored on disk
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PresetEntry {
     pub index: i32,
@@ -11,22 +14,53 @@ pub struct PresetEntry {
 }
 
 
+/// A loaded bank
 #[derive(Debug, Clone)]
 pub struct PresetData {
+    /// Folder the bank was loaded from and is saved to
     pub path: String,
     pub preset_type: PresetType,
+    /// Presets keyed by slot index
     pub presets: HashMap<i32, LoadedPresetEntry>,
-    pub name: String
+    /// Display name, the name of the bank folder
+    pub name: String,
+    /// Why the metadata file could not be read. The bank is loaded without metadata then, and
+    /// saving it leaves the file as it is unless metadata is added.
+    pub metadata_error: Option<String>,
+    /// Settings files of list entries that were not loaded because an earlier entry of the list
+    /// has their slot. Saving leaves them out, repairing the bank renumbers them.
+    pub displaced: Vec<String>,
 }
 
-#[derive(Debug, Clone)]
+/// A preset with its image and settings read into memory
+#[derive(Debug, Clone, PartialEq)]
 pub struct LoadedPresetEntry {
+    /// Slot the preset was loaded from
     pub original_index: i32,
     pub original_side: Side,
     pub image_filename: String,
     pub preset_filename: String,
     pub image: ImageData,
-    pub preset_data: String
+    /// Contents of the settings JSON file
+    pub preset_data: String,
+    /// Modification time of the settings file when it was loaded, or when the settings were last
+    /// edited. Saving gives the settings file this time.
+    pub modified: Option<SystemTime>,
+    /// Descriptive information kept in the bank's metadata file
+    pub metadata: PresetMetadata,
+    /// Set when the files of the preset could not be loaded
+    pub broken: Option<BrokenPreset>
+}
+
+/// Why a preset could not be loaded, with its files kept as they were read so that saving the
+/// bank writes them back unchanged
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrokenPreset {
+    pub reason: String,
+    /// Contents of the image file, `None` when it could not be read
+    pub image_file: Option<Vec<u8>>,
+    /// Contents of the settings file, `None` when it could not be read
+    pub settings_file: Option<Vec<u8>>
 }
 
 impl LoadedPresetEntry {
@@ -36,7 +70,8 @@ impl LoadedPresetEntry {
         image_filename: String,
         preset_filename: String,
         image: ImageData,
-        preset_data: String
+        preset_data: String,
+        modified: Option<SystemTime>
     ) -> Self {
         Self {
             original_index,
@@ -44,12 +79,37 @@ impl LoadedPresetEntry {
             image_filename,
             preset_filename,
             image,
-            preset_data
+            preset_data,
+            modified,
+            metadata: PresetMetadata::default(),
+            broken: None
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// Human information about a preset that the synth itself does not use
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct PresetMetadata {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub author: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub category: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub notes: String
+}
+
+impl PresetMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == PresetMetadata::default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct PointF {
     pub x: f32,
     pub y: f32,
@@ -67,6 +127,7 @@ impl From<(f32, f32)> for PointF {
     }
 }
 
+/// One of the two banks open side by side in the editor
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum Side {
     Left,
@@ -82,6 +143,7 @@ impl Side {
     }
 }
 
+/// The two kinds of banks, they use different file names and can share a folder
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum PresetType {
     Timbre,
@@ -0,0 +1,391 @@
+use preset_manager::presets::backup::list_backups;
+use preset_manager::presets::validate::{check_bank, repair_bank, BankProblem, Repair};
+use preset_manager::presets::{load_presets, open_presets, operations, save_presets};
+use preset_manager::types::{PresetData, PresetType, Side};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+/// Files in the fixture banks that do not belong to the bank and must survive every save
+const TIMBRE_FOREIGN_FILES: [&str; 2] = ["notes.txt", "recordings/take1.txt"];
+const SYSTEM_FOREIGN_FILES: [&str; 2] = ["notes.txt", "settings_system_auto.json"];
+
+/// Every file below `dir` with its contents, keyed by its path relative to `dir`
+fn read_tree(dir: &Path) -> BTreeMap<String, Vec<u8>> {
+    fn visit(root: &Path, dir: &Path, files: &mut BTreeMap<String, Vec<u8>>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                visit(root, &path, files);
+            } else {
+                let name = path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/");
+                files.insert(name, fs::read(&path).unwrap());
+            }
+        }
+    }
+
+    let mut files = BTreeMap::new();
+    visit(dir, dir, &mut files);
+    files
+}
+
+fn fixture(name: &str) -> BTreeMap<String, Vec<u8>> {
+    read_tree(&Path::new(FIXTURES).join(name))
+}
+
+/// A temporary folder holding the files of the given fixture banks
+fn copy_fixtures(names: &[&str]) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    for name in names {
+        for (file, contents) in fixture(name) {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+    }
+    dir
+}
+
+fn load(dir: &Path, preset_type: PresetType) -> PresetData {
+    load_presets(dir.to_str().unwrap(), preset_type, Side::Left).unwrap()
+}
+
+fn save(preset_data: &PresetData) {
+    save_presets(&preset_data.path, &preset_data.presets, preset_data.preset_type).unwrap();
+}
+
+fn file(dir: &TempDir, name: &str) -> Vec<u8> {
+    fs::read(dir.path().join(name)).unwrap()
+}
+
+#[test]
+fn saving_into_an_empty_folder_reproduces_the_bank_files() {
+    for (name, preset_type, foreign_files) in [
+        ("timbre", PresetType::Timbre, TIMBRE_FOREIGN_FILES),
+        ("system", PresetType::System, SYSTEM_FOREIGN_FILES),
+    ] {
+        let source = copy_fixtures(&[name]);
+        let target = TempDir::new().unwrap();
+        let preset_data = load(source.path(), preset_type);
+        save_presets(target.path().to_str().unwrap(), &preset_data.presets, preset_type).unwrap();
+
+        let mut expected = fixture(name);
+        expected.retain(|file, _| !foreign_files.contains(&file.as_str()));
+        assert_eq!(read_tree(target.path()), expected, "{name} bank");
+    }
+}
+
+#[test]
+fn saving_an_unchanged_bank_in_place_leaves_every_file_as_it_was() {
+    for (name, preset_type) in [("timbre", PresetType::Timbre), ("system", PresetType::System)] {
+        let dir = copy_fixtures(&[name]);
+        save(&load(dir.path(), preset_type));
+
+        assert_eq!(read_tree(dir.path()), fixture(name), "{name} bank");
+    }
+}
+
+#[test]
+fn saving_keeps_the_formatting_of_settings_and_writes_the_list_compactly() {
+    let dir = copy_fixtures(&["formatted"]);
+    let preset_data = load(dir.path(), PresetType::Timbre);
+    save(&preset_data);
+
+    // Settings are written back as the synth formatted them
+    let mut expected = fixture("formatted");
+    let list = expected.remove("preset_list.json").unwrap();
+    let mut files = read_tree(dir.path());
+    let saved_list = files.remove("preset_list.json").unwrap();
+    assert_eq!(files, expected);
+    // The preset list is always written without whitespace, holding the same entries
+    let compact = serde_json::from_slice::<serde_json::Value>(&list).unwrap().to_string();
+    assert_eq!(String::from_utf8(saved_list).unwrap(), compact);
+    assert_eq!(load(dir.path(), PresetType::Timbre).presets.len(), 2);
+}
+
+#[test]
+fn loading_keeps_the_gaps_between_presets() {
+    let dir = copy_fixtures(&["timbre"]);
+    let preset_data = load(dir.path(), PresetType::Timbre);
+
+    let mut indices: Vec<i32> = preset_data.presets.keys().copied().collect();
+    indices.sort();
+    assert_eq!(indices, [0, 1, 4, 7, 13]);
+    assert!(preset_data.presets.values().all(|p| p.broken.is_none()));
+}
+
+#[test]
+fn broken_presets_are_written_back_and_moved_byte_for_byte() {
+    let dir = copy_fixtures(&["broken"]);
+    let preset_data = load(dir.path(), PresetType::Timbre);
+    // A truncated image and settings that are not JSON
+    assert!(preset_data.presets[&0].broken.is_none());
+    assert!(preset_data.presets[&1].broken.is_some());
+    assert!(preset_data.presets[&2].broken.is_some());
+
+    save(&preset_data);
+    assert_eq!(read_tree(dir.path()), fixture("broken"));
+
+    let mut preset_lists = HashMap::from([(Side::Left, preset_data)]);
+    operations::move_preset(&mut preset_lists, 1, Side::Left, 6, Side::Left).unwrap();
+    operations::move_preset(&mut preset_lists, 2, Side::Left, 7, Side::Left).unwrap();
+    save(&preset_lists[&Side::Left]);
+
+    let original = fixture("broken");
+    for (from, to) in [("0_1", "1_0"), ("0_2", "1_1")] {
+        for (prefix, extension) in [("settings_timbre", "json"), ("image_timbre", "lsi")] {
+            let moved = format!("{prefix}{to}.{extension}");
+            let source = format!("{prefix}{from}.{extension}");
+            assert_eq!(file(&dir, &moved), original[&source], "{moved}");
+            assert!(!dir.path().join(&source).exists(), "{source}");
+        }
+    }
+}
+
+#[test]
+fn moving_a_preset_renames_its_files_after_the_new_slot() {
+    let dir = copy_fixtures(&["timbre"]);
+    let mut preset_lists = HashMap::from([(Side::Left, load(dir.path(), PresetType::Timbre))]);
+    operations::move_preset(&mut preset_lists, 1, Side::Left, 9, Side::Left).unwrap();
+    save(&preset_lists[&Side::Left]);
+
+    let original = fixture("timbre");
+    assert_eq!(file(&dir, "settings_timbre1_3.json"), original["settings_timbre0_1.json"]);
+    assert_eq!(file(&dir, "image_timbre1_3.lsi"), original["image_timbre0_1.lsi"]);
+    assert!(!dir.path().join("settings_timbre0_1.json").exists());
+    assert!(!dir.path().join("image_timbre0_1.lsi").exists());
+
+    let reloaded = load(dir.path(), PresetType::Timbre);
+    let moved = &reloaded.presets[&9];
+    assert_eq!(moved.preset_filename, "settings_timbre1_3.json");
+    assert_eq!(moved.image_filename, "image_timbre1_3.lsi");
+    // The metadata follows the preset to its new file name
+    assert_eq!(moved.metadata.author, "Ana");
+}
+
+#[test]
+fn saving_keeps_the_modification_time_of_moved_settings() {
+    let dir = copy_fixtures(&["timbre"]);
+    let long_ago = SystemTime::UNIX_EPOCH + Duration::from_secs(1_500_000_000);
+    let settings = fs::File::options()
+        .write(true)
+        .open(dir.path().join("settings_timbre0_1.json"))
+        .unwrap();
+    settings.set_modified(long_ago).unwrap();
+    drop(settings);
+
+    let mut preset_lists = HashMap::from([(Side::Left, load(dir.path(), PresetType::Timbre))]);
+    operations::move_preset(&mut preset_lists, 1, Side::Left, 9, Side::Left).unwrap();
+    save(&preset_lists[&Side::Left]);
+
+    let modified = fs::metadata(dir.path().join("settings_timbre1_3.json"))
+        .and_then(|m| m.modified())
+        .unwrap();
+    assert_eq!(modified, long_ago);
+    assert_eq!(load(dir.path(), PresetType::Timbre).presets[&9].modified, Some(long_ago));
+}
+
+#[test]
+fn an_unreadable_metadata_file_is_skipped_on_load_and_kept_on_save() {
+    let dir = copy_fixtures(&["timbre"]);
+    let broken = b"{\"settings_timbre0_0.json\": {\"name\": ".to_vec();
+    fs::write(dir.path().join("preset_metadata.json"), &broken).unwrap();
+
+    let mut preset_lists = HashMap::from([(Side::Left, load(dir.path(), PresetType::Timbre))]);
+    let preset_data = &preset_lists[&Side::Left];
+    assert!(preset_data.metadata_error.is_some());
+    assert_eq!(preset_data.presets.len(), 5);
+    assert!(preset_data.presets.values().all(|p| p.metadata.is_empty()));
+
+    operations::move_preset(&mut preset_lists, 1, Side::Left, 9, Side::Left).unwrap();
+    save(&preset_lists[&Side::Left]);
+    assert_eq!(file(&dir, "preset_metadata.json"), broken);
+}
+
+#[test]
+fn swapping_presets_swaps_their_files() {
+    let dir = copy_fixtures(&["timbre"]);
+    let mut preset_lists = HashMap::from([(Side::Left, load(dir.path(), PresetType::Timbre))]);
+    operations::move_preset(&mut preset_lists, 0, Side::Left, 7, Side::Left).unwrap();
+    save(&preset_lists[&Side::Left]);
+
+    let original = fixture("timbre");
+    assert_eq!(file(&dir, "settings_timbre0_0.json"), original["settings_timbre1_1.json"]);
+    assert_eq!(file(&dir, "settings_timbre1_1.json"), original["settings_timbre0_0.json"]);
+    assert_eq!(file(&dir, "image_timbre0_0.lsi"), original["image_timbre1_1.lsi"]);
+    assert_eq!(file(&dir, "image_timbre1_1.lsi"), original["image_timbre0_0.lsi"]);
+}
+
+#[test]
+fn deleting_every_preset_keeps_unrelated_files() {
+    for (name, preset_type, foreign_files) in [
+        ("timbre", PresetType::Timbre, TIMBRE_FOREIGN_FILES),
+        ("system", PresetType::System, SYSTEM_FOREIGN_FILES),
+    ] {
+        let dir = copy_fixtures(&[name]);
+        let mut preset_lists = HashMap::from([(Side::Left, load(dir.path(), preset_type))]);
+        let indices: Vec<i32> = preset_lists[&Side::Left].presets.keys().copied().collect();
+        operations::delete_presets(&mut preset_lists, Side::Left, &indices).unwrap();
+        save(&preset_lists[&Side::Left]);
+
+        let original = fixture(name);
+        let files = read_tree(dir.path());
+        for foreign_file in foreign_files {
+            assert_eq!(files[foreign_file], original[foreign_file], "{name} bank");
+        }
+        // Only the empty preset list is left of the bank
+        assert_eq!(files.len(), foreign_files.len() + 1, "{name} bank");
+        assert!(load(dir.path(), preset_type).presets.is_empty());
+    }
+}
+
+#[test]
+fn saving_one_bank_leaves_the_other_bank_in_a_shared_folder_alone() {
+    let dir = copy_fixtures(&["timbre", "system"]);
+    let mut preset_lists = HashMap::from([(Side::Left, load(dir.path(), PresetType::Timbre))]);
+    operations::move_preset(&mut preset_lists, 13, Side::Left, 2, Side::Left).unwrap();
+    save(&preset_lists[&Side::Left]);
+
+    let files = read_tree(dir.path());
+    for (file, contents) in fixture("system") {
+        assert_eq!(files[&file], contents, "{file}");
+    }
+    assert_eq!(load(dir.path(), PresetType::System).presets.len(), 3);
+}
+
+#[test]
+fn backups_made_in_the_same_second_are_listed_newest_first() {
+    let dir = copy_fixtures(&["timbre"]);
+    let names = ["2024-05-01_10-00-00", "2024-05-01_10-00-00_9", "2024-05-01_10-00-00_10"];
+    for name in names {
+        let backup = dir.path().join(".backups").join(name);
+        fs::create_dir_all(&backup).unwrap();
+        fs::write(backup.join("preset_list.json"), file(&dir, "preset_list.json")).unwrap();
+    }
+
+    let backups = list_backups(dir.path().to_str().unwrap(), PresetType::Timbre).unwrap();
+    let listed: Vec<&str> = backups
+        .iter()
+        .map(|b| Path::new(&b.path).file_name().unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(listed, [names[2], names[1], names[0]]);
+    // Counted from the preset list alone, the backups hold no preset files
+    assert!(backups.iter().all(|b| b.preset_count == Some(5)));
+}
+
+#[test]
+fn checking_a_bank_reports_an_interrupted_save_without_finishing_it() {
+    let dir = copy_fixtures(&["timbre"]);
+    let staging = dir.path().join(".preset_manager_staging_timbre");
+    fs::create_dir(&staging).unwrap();
+    fs::write(staging.join(".complete"), "").unwrap();
+    let before = read_tree(dir.path());
+
+    let check = check_bank(dir.path().to_str().unwrap(), PresetType::Timbre);
+    assert!(matches!(check.problems[..], [BankProblem::InterruptedSave(_)]));
+    assert_eq!(read_tree(dir.path()), before);
+}
+
+#[test]
+fn settings_that_are_not_text_are_reported_as_unreadable_rather_than_missing() {
+    let dir = copy_fixtures(&["timbre"]);
+    fs::write(dir.path().join("settings_timbre0_1.json"), [0xff, 0xfe, b'{', b'}']).unwrap();
+
+    let check = check_bank(dir.path().to_str().unwrap(), PresetType::Timbre);
+    assert!(matches!(
+        &check.problems[..],
+        [BankProblem::UnreadableFile { index: 1, file, .. }] if file == "settings_timbre0_1.json"
+    ));
+    assert!(load(dir.path(), PresetType::Timbre).presets[&1].broken.is_some());
+}
+
+#[test]
+fn the_first_of_two_entries_in_a_slot_is_loaded_and_the_other_is_reported() {
+    let dir = copy_fixtures(&["timbre"]);
+    let second = r#"{"index":1,"image_filename":"image_timbre9_1.lsi","#.to_string()
+        + r#""preset_filename":"settings_timbre9_1.json"}"#;
+    let list = String::from_utf8(file(&dir, "preset_list.json")).unwrap();
+    let list = list.replace(']', &format!(",{second}]"));
+    fs::write(dir.path().join("preset_list.json"), list).unwrap();
+    fs::write(dir.path().join("settings_timbre9_1.json"), "{}").unwrap();
+    fs::write(dir.path().join("image_timbre9_1.lsi"), "").unwrap();
+
+    let preset_data = load(dir.path(), PresetType::Timbre);
+    assert_eq!(preset_data.presets[&1].preset_filename, "settings_timbre0_1.json");
+    assert_eq!(preset_data.displaced, ["settings_timbre9_1.json"]);
+    let check = check_bank(dir.path().to_str().unwrap(), PresetType::Timbre);
+    assert_eq!(check.presets[&1].preset_filename, "settings_timbre0_1.json");
+
+    // Repairing keeps the loaded preset in the slot and moves the other one to a free slot
+    let path = dir.path().to_str().unwrap();
+    repair_bank(path, PresetType::Timbre, &[Repair::Renumber, Repair::Rename]).unwrap();
+    let repaired = load(dir.path(), PresetType::Timbre);
+    assert_eq!(file(&dir, "settings_timbre0_1.json"), fixture("timbre")["settings_timbre0_1.json"]);
+    assert_eq!(repaired.presets.len(), 6);
+    assert!(repaired.displaced.is_empty());
+}
+
+#[test]
+fn repairs_that_would_lose_presets_are_refused() {
+    let dir = copy_fixtures(&["timbre"]);
+    fs::write(dir.path().join("settings_timbre3_3.json"), "{}").unwrap();
+    let before = read_tree(dir.path());
+    let path = dir.path().to_str().unwrap();
+
+    assert!(repair_bank(path, PresetType::Timbre, &[Repair::Renumber]).is_err());
+    assert_eq!(read_tree(dir.path()), before);
+
+    let summary = repair_bank(path, PresetType::Timbre, &[Repair::AdoptOrphans]).unwrap();
+    assert_eq!(summary.adopted, 1);
+    assert_eq!(load(dir.path(), PresetType::Timbre).presets.len(), 6);
+}
+
+#[test]
+fn opening_a_bank_finishes_a_save_interrupted_after_it_was_staged() {
+    let dir = copy_fixtures(&["timbre"]);
+    let mut preset_lists = HashMap::from([(Side::Left, load(dir.path(), PresetType::Timbre))]);
+    operations::move_preset(&mut preset_lists, 1, Side::Left, 9, Side::Left).unwrap();
+    let saved = TempDir::new().unwrap();
+    let presets = &preset_lists[&Side::Left].presets;
+    save_presets(saved.path().to_str().unwrap(), presets, PresetType::Timbre).unwrap();
+
+    // The crash came after the new bank was staged and while the old files were moved aside
+    let staging = dir.path().join(".preset_manager_staging_timbre");
+    let replaced = dir.path().join(".preset_manager_replaced_timbre");
+    fs::create_dir(&staging).unwrap();
+    fs::create_dir(&replaced).unwrap();
+    for (file, contents) in read_tree(saved.path()) {
+        fs::write(staging.join(file), contents).unwrap();
+    }
+    fs::write(staging.join(".complete"), "").unwrap();
+    fs::rename(dir.path().join("preset_list.json"), replaced.join("preset_list.json")).unwrap();
+
+    let path = dir.path().to_str().unwrap();
+    assert!(load_presets(path, PresetType::Timbre, Side::Left).is_err());
+    let opened = open_presets(path, PresetType::Timbre, Side::Left).unwrap();
+    assert!(opened.presets.contains_key(&9));
+
+    let mut expected = read_tree(saved.path());
+    for foreign_file in TIMBRE_FOREIGN_FILES {
+        expected.insert(foreign_file.to_string(), fixture("timbre")[foreign_file].clone());
+    }
+    assert_eq!(read_tree(dir.path()), expected);
+    assert!(!staging.exists() && !replaced.exists());
+}
+
+#[test]
+fn opening_a_bank_drops_a_save_interrupted_before_it_was_staged() {
+    let dir = copy_fixtures(&["timbre"]);
+    // Part of the new bank was written when the crash came, without the complete marker
+    let staging = dir.path().join(".preset_manager_staging_timbre");
+    fs::create_dir(&staging).unwrap();
+    fs::write(staging.join("settings_timbre0_0.json"), "{}").unwrap();
+
+    let opened = open_presets(dir.path().to_str().unwrap(), PresetType::Timbre, Side::Left);
+    assert_eq!(opened.unwrap().presets.len(), 5);
+    assert_eq!(read_tree(dir.path()), fixture("timbre"));
+}
@@ -0,0 +1,81 @@
+use preset_manager::presets::lsi_parser::{parse_lsi_image, LsiError};
+use preset_manager::presets::lsi_writer::write_lsi_image;
+use preset_manager::types::{ImageData, PointF};
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+fn image() -> impl Strategy<Value = ImageData> {
+    let point = (0.0f32..=1.0, 0.0f32..=1.0).prop_map(PointF::from);
+    vec(vec(point, 0..40), 0..20)
+}
+
+fn line(count: i32, coordinates: &[f32]) -> Vec<u8> {
+    let mut data = count.to_le_bytes().to_vec();
+    for coordinate in coordinates {
+        data.extend(coordinate.to_le_bytes());
+    }
+    data
+}
+
+fn error_offset(data: &[u8]) -> usize {
+    parse_lsi_image(data).unwrap_err().offset
+}
+
+proptest! {
+    #[test]
+    fn written_images_parse_back(image in image()) {
+        prop_assert_eq!(parse_lsi_image(&write_lsi_image(&image)), Ok(image));
+    }
+
+    #[test]
+    fn parsed_data_writes_back_unchanged(data in vec(any::<u8>(), 0..256)) {
+        if let Ok(image) = parse_lsi_image(&data) {
+            prop_assert_eq!(write_lsi_image(&image), data);
+        }
+    }
+
+    #[test]
+    fn truncated_images_are_rejected(image in image(), cut in any::<prop::sample::Index>()) {
+        let data = write_lsi_image(&image);
+        prop_assume!(!data.is_empty());
+        let cut = cut.index(data.len());
+        match parse_lsi_image(&data[..cut]) {
+            // Cutting between two lines leaves the lines before the cut
+            Ok(parsed) => prop_assert_eq!(&parsed[..], &image[..parsed.len()]),
+            Err(LsiError { offset, .. }) => prop_assert!(offset < cut),
+        }
+    }
+}
+
+#[test]
+fn empty_data_is_an_empty_image() {
+    assert_eq!(parse_lsi_image(&[]), Ok(Vec::new()));
+}
+
+#[test]
+fn negative_count_is_rejected_at_the_count() {
+    let mut data = line(1, &[0.5, 0.5]);
+    data.extend(line(-1, &[]));
+    assert_eq!(error_offset(&data), 12);
+}
+
+#[test]
+fn count_larger_than_the_data_is_rejected_before_allocating() {
+    assert_eq!(error_offset(&line(i32::MAX, &[0.5, 0.5])), 0);
+    assert_eq!(error_offset(&line(2, &[0.5, 0.5, 0.5])), 0);
+}
+
+#[test]
+fn incomplete_count_is_rejected() {
+    let mut data = line(0, &[]);
+    data.extend([1, 0]);
+    assert_eq!(error_offset(&data), 4);
+}
+
+#[test]
+fn non_finite_and_out_of_range_coordinates_are_rejected_where_they_are() {
+    assert_eq!(error_offset(&line(1, &[f32::NAN, 0.5])), 4);
+    assert_eq!(error_offset(&line(1, &[0.5, f32::INFINITY])), 8);
+    assert_eq!(error_offset(&line(2, &[0.5, 0.5, 1.5, 0.5])), 12);
+    assert_eq!(error_offset(&line(1, &[0.5, -0.1])), 8);
+}